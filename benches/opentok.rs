@@ -0,0 +1,57 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use opentok_server::{OpenTok, SessionId, StreamInfo, TokenRole};
+use serde::Deserialize;
+
+/// Shape of the OpenTok API list responses.
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct ListResponse<T> {
+    count: usize,
+    items: Vec<T>,
+}
+
+fn token_generation(c: &mut Criterion) {
+    let opentok = OpenTok::new("12345678".into(), "0123456789abcdef".into());
+    let session_id = SessionId::from(
+        "1_MX4xMjM0NTY3OH5-MTYzNDU2Nzg5MDEyM35hYmNkZWZnaGlqa2xtbm9wcXJzdHV2d3h5en5-fg",
+    );
+    let mut group = c.benchmark_group("token_generation");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("generate_token", |b| {
+        b.iter(|| opentok.generate_token(black_box(&session_id), TokenRole::Publisher))
+    });
+    group.finish();
+}
+
+fn jwt_creation(c: &mut Criterion) {
+    c.bench_function("auth_header", |b| {
+        b.iter(|| {
+            opentok_server::benchmarks::auth_header(
+                black_box("12345678"),
+                black_box("0123456789abcdef"),
+            )
+        })
+    });
+}
+
+fn response_parsing(c: &mut Criterion) {
+    let items = (0..1000)
+        .map(|i| {
+            format!(
+                r#"{{"id":"8b732909-0a06-46a2-8ea8-074e64d4{:04}","videoType":"camera","name":"Publisher {}","layoutClassList":["full","focus"]}}"#,
+                i, i
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    let body = format!(r#"{{"count":1000,"items":[{}]}}"#, items);
+    let mut group = c.benchmark_group("response_parsing");
+    group.throughput(Throughput::Bytes(body.len() as u64));
+    group.bench_function("1000_streams", |b| {
+        b.iter(|| serde_json::from_str::<ListResponse<StreamInfo>>(black_box(&body)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, token_generation, jwt_creation, response_parsing);
+criterion_main!(benches);
@@ -0,0 +1,94 @@
+use crate::{OpenTokError, ParsedSessionId};
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+macro_rules! id_type {
+    ($(#[$attr:meta])* $name:ident) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+        #[serde(transparent)]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str(&self.0)
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(id: String) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(id: &str) -> Self {
+                Self(id.into())
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+    };
+}
+
+id_type!(
+    /// Unique session identifier.
+    SessionId
+);
+id_type!(
+    /// Identifier of a stream published to a session.
+    StreamId
+);
+id_type!(
+    /// Identifier of a client connection to a session.
+    ConnectionId
+);
+id_type!(
+    /// Identifier of an archive.
+    ArchiveId
+);
+id_type!(
+    /// Identifier of a live streaming broadcast.
+    BroadcastId
+);
+
+impl SessionId {
+    /// Decodes the components of this session ID. See `ParsedSessionId`.
+    pub fn parse(&self) -> Result<ParsedSessionId, OpenTokError> {
+        ParsedSessionId::parse(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids() {
+        let session_id = SessionId::from("1_MX4");
+        assert_eq!(session_id.to_string(), "1_MX4");
+        assert_eq!(serde_json::to_string(&session_id).unwrap(), "\"1_MX4\"");
+        assert_eq!(
+            serde_json::from_str::<StreamId>("\"8b73\"").unwrap(),
+            StreamId::from("8b73".to_string())
+        );
+        assert_eq!(String::from(session_id), "1_MX4");
+    }
+}
@@ -0,0 +1,323 @@
+//! Types of the OpenTok server SDK, without its HTTP client: identifiers,
+//! session options, stream information, errors and the verification of
+//! signed callbacks.
+//!
+//! These are re-exported by the `opentok-server` crate. Depend on this
+//! crate alone where only the types are needed, such as in services
+//! receiving OpenTok callbacks or in WASM edge functions.
+
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::expect_used,
+        clippy::indexing_slicing,
+        clippy::panic,
+        clippy::string_slice,
+        clippy::unwrap_used
+    )
+)]
+
+extern crate rustc_serialize;
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+use std::time::Duration;
+use thiserror::Error;
+
+mod ids;
+mod session_id;
+pub mod time;
+mod webhook;
+
+pub use ids::{ArchiveId, BroadcastId, ConnectionId, SessionId, StreamId};
+pub use session_id::ParsedSessionId;
+pub use webhook::WebhookVerifier;
+
+/// OpenTokError enumerates all possible errors returned by this library.
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum OpenTokError {
+    #[error("Bad request {0}")]
+    BadRequest(String),
+    #[error("Cannot encode request")]
+    EncodingError,
+    #[error("OpenTok server error {0}")]
+    ServerError(String),
+    #[error("Unexpected response {0}")]
+    UnexpectedResponse(String),
+    #[error("Not found {0}")]
+    NotFound(String),
+    #[error("Forbidden {0}")]
+    Forbidden(String),
+    #[error("Rate limited, retry after {retry_after:?}")]
+    RateLimited { retry_after: Option<Duration> },
+    #[error("OpenTok API error {status} (code {code:?}): {message}")]
+    ApiError {
+        code: Option<i64>,
+        message: String,
+        status: u16,
+    },
+    #[error("GraphQL errors: {}", .0.join("; "))]
+    GraphQl(Vec<String>),
+    #[error("Request timed out")]
+    Timeout,
+    #[error("Connection error {0}")]
+    ConnectionError(String),
+    #[error("Invalid configuration {0}")]
+    Config(String),
+    #[error("Invalid use of the client: {0}")]
+    Misuse(String),
+    #[error("Unknown error")]
+    __Unknown,
+}
+
+impl OpenTokError {
+    /// Whether the error is likely temporary, such as server errors, rate
+    /// limiting or connection failures, so that the same request may
+    /// succeed later.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            OpenTokError::ServerError(_)
+            | OpenTokError::RateLimited { .. }
+            | OpenTokError::Timeout
+            | OpenTokError::ConnectionError(_) => true,
+            OpenTokError::ApiError { status, .. } => *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
+}
+
+/// Determines whether a session will transmit streams using the OpenTok Media Router
+/// or not.
+#[derive(Debug, PartialEq)]
+pub enum MediaMode {
+    /// The session will try to transmit streams directly between clients.
+    Relayed,
+    /// The session will transmit streams using the OpenTok Media Router.
+    Routed,
+}
+
+impl fmt::Display for MediaMode {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+/// Determines whether a session is automatically archived or not.
+/// Archiving is currently unsupported.
+#[derive(Debug)]
+pub enum ArchiveMode {
+    /// The session will always be archived automatically.
+    Always,
+    /// A POST request to /archive is required to archive the session.
+    /// Currently unsupported.
+    Manual,
+}
+
+impl fmt::Display for ArchiveMode {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+/// OpenTok Session options to be provided at Session creation time.
+#[derive(Default)]
+pub struct SessionOptions<'a> {
+    /// An IP address that the OpenTok servers will use to situate the session in the global
+    /// OpenTok network. If you do not set a location hint, the OpenTok servers will be based
+    /// on the first client connecting to the session.
+    pub location: Option<&'a str>,
+    /// Determines whether the session will transmit streams using the OpenTok Media Router
+    /// ("routed") or not ("relayed"). By default, the setting is "relayed".
+    /// With the media_mode parameter set to "relayed", the session will attempt to transmit
+    /// streams directly between clients. If clients cannot connect due to firewall restrictions,
+    /// the session uses the OpenTok TURN server to relay audio-video streams.
+    pub media_mode: Option<MediaMode>,
+    /// Whether the session is automatically archived ("always") or not ("manual").
+    /// By default, the setting is "manual". To archive the session (either automatically or not),
+    /// you must set the media_mode parameter to "routed".
+    /// Archiving is currently unsupported.
+    pub archive_mode: Option<ArchiveMode>,
+}
+
+impl<'a> SessionOptions<'a> {
+    /// Create a builder of session options, checking them when built.
+    pub fn builder() -> SessionOptionsBuilder<'a> {
+        SessionOptionsBuilder {
+            options: SessionOptions::default(),
+        }
+    }
+
+    /// Options for calls between two participants that may later need
+    /// the features of the OpenTok Media Router, such as archiving or
+    /// audio fallback: routed and manually archived.
+    pub fn one_to_one() -> Self {
+        Self {
+            media_mode: Some(MediaMode::Routed),
+            archive_mode: Some(ArchiveMode::Manual),
+            ..Default::default()
+        }
+    }
+
+    /// Options for sessions with a few presenters and many viewers, all of
+    /// which are kept: routed and archived automatically. Set `location`
+    /// to the IP address of a server in the region of the audience, such as
+    /// Europe, to host the session close to it.
+    pub fn webinar() -> Self {
+        Self {
+            media_mode: Some(MediaMode::Routed),
+            archive_mode: Some(ArchiveMode::Always),
+            ..Default::default()
+        }
+    }
+
+    /// Options for calls between two participants streaming directly to
+    /// each other, for the lowest latency and no media server cost:
+    /// relayed and never archived.
+    pub fn p2p_calling() -> Self {
+        Self {
+            media_mode: Some(MediaMode::Relayed),
+            archive_mode: Some(ArchiveMode::Manual),
+            ..Default::default()
+        }
+    }
+
+    /// Checks that the options are consistent, failing with
+    /// `OpenTokError::BadRequest` otherwise: the location hint must be an IP
+    /// address, and sessions archived automatically cannot be relayed.
+    pub fn validate(&self) -> Result<(), OpenTokError> {
+        if let Some(location) = self.location {
+            if location.parse::<IpAddr>().is_err() {
+                return Err(OpenTokError::BadRequest(format!(
+                    "Location hint {} is not an IP address",
+                    location
+                )));
+            }
+        }
+        if matches!(self.archive_mode, Some(ArchiveMode::Always))
+            && self.media_mode == Some(MediaMode::Relayed)
+        {
+            return Err(OpenTokError::BadRequest(
+                "Sessions archived automatically must use the routed media mode".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Builder for `SessionOptions`.
+pub struct SessionOptionsBuilder<'a> {
+    options: SessionOptions<'a>,
+}
+
+impl<'a> SessionOptionsBuilder<'a> {
+    /// See `SessionOptions::location`.
+    pub fn location(mut self, location: &'a str) -> Self {
+        self.options.location = Some(location);
+        self
+    }
+
+    /// See `SessionOptions::media_mode`.
+    pub fn media_mode(mut self, media_mode: MediaMode) -> Self {
+        self.options.media_mode = Some(media_mode);
+        self
+    }
+
+    /// See `SessionOptions::archive_mode`.
+    pub fn archive_mode(mut self, archive_mode: ArchiveMode) -> Self {
+        self.options.archive_mode = Some(archive_mode);
+        self
+    }
+
+    /// Returns the options, or `OpenTokError::BadRequest` if they are
+    /// inconsistent. See `SessionOptions::validate`.
+    pub fn build(self) -> Result<SessionOptions<'a>, OpenTokError> {
+        self.options.validate()?;
+        Ok(self.options)
+    }
+}
+
+/// Form body of the request creating a session.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSessionBody<'a> {
+    /// `always` or `manual`.
+    pub archive_mode: String,
+    pub location: Option<&'a str>,
+    /// `enabled` for relayed sessions, `disabled` for routed ones.
+    #[serde(rename = "p2p.preference")]
+    pub p2p_preference: &'a str,
+}
+
+impl<'a> From<SessionOptions<'a>> for CreateSessionBody<'a> {
+    fn from(options: SessionOptions) -> CreateSessionBody {
+        CreateSessionBody {
+            archive_mode: options
+                .archive_mode
+                .map(|mode| mode.to_string())
+                .unwrap_or_else(|| "manual".into()),
+            location: options.location,
+            p2p_preference: options
+                .media_mode
+                .map(|mode| {
+                    if mode == MediaMode::Relayed {
+                        "enabled"
+                    } else {
+                        "disabled"
+                    }
+                })
+                .unwrap_or("disabled"),
+        }
+    }
+}
+
+/// Session in the response to the request creating a session, which is a
+/// JSON array of them.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct CreateSessionResponse {
+    pub session_id: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenRole {
+    Publisher,
+    Subscriber,
+    Moderator,
+}
+
+impl fmt::Display for TokenRole {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoType {
+    Camera,
+    Screen,
+    Custom,
+}
+
+impl fmt::Display for VideoType {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+/// Information about a stream published to a session, as returned by
+/// `OpenTok::get_stream_info`. Serializes to the same JSON representation
+/// as the OpenTok API's.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamInfo {
+    pub id: StreamId,
+    /// Whether the stream comes from a camera, a screen share or a custom
+    /// video source.
+    pub video_type: VideoType,
+    /// Name given to the stream by the publisher, possibly empty.
+    pub name: String,
+    /// Layout classes assigned to the stream, used in archive and broadcast
+    /// layouts.
+    pub layout_class_list: Vec<String>,
+}
@@ -0,0 +1,117 @@
+//! Decoding of OpenTok session IDs.
+//!
+//! A session ID is a version prefix such as `1_` followed by a URL safe
+//! base64 encoding of `~` separated fields, starting with the API key of
+//! the project the session belongs to, the location hint it was created
+//! with and its creation time.
+
+use crate::time::{SystemTime, UNIX_EPOCH};
+use crate::OpenTokError;
+
+use std::time::Duration;
+
+/// Components of a session ID.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedSessionId {
+    /// API key of the project the session belongs to.
+    pub api_key: String,
+    /// Location hint the session was created with, if any.
+    pub location: Option<String>,
+    pub created_at: SystemTime,
+}
+
+impl ParsedSessionId {
+    /// Decodes `session_id`, failing with `OpenTokError::BadRequest` if it
+    /// is not a well formed OpenTok session ID.
+    pub fn parse(session_id: &str) -> Result<Self, OpenTokError> {
+        let invalid = || OpenTokError::BadRequest(format!("Invalid session ID {}", session_id));
+        let encoded = match session_id.split_once('_') {
+            Some((version, encoded))
+                if !version.is_empty() && version.bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                encoded.trim_end_matches('=')
+            }
+            _ => return Err(invalid()),
+        };
+        // The last characters of the encoding only cover the trailing random
+        // part of the ID, and may not form valid base64 on their own, so only
+        // complete groups of four characters are decoded. Encodings with
+        // non-ASCII characters, which may not be cut there, are invalid.
+        let complete = encoded
+            .get(..encoded.len() - encoded.len() % 4)
+            .ok_or_else(invalid)?;
+        let decoded = base64::decode_config(complete, base64::URL_SAFE).map_err(|_| invalid())?;
+        let decoded = String::from_utf8_lossy(&decoded);
+        let (api_key, location, created_at) = match decoded.split('~').collect::<Vec<_>>()[..] {
+            [_, api_key, location, created_at, _, ..] => (api_key, location, created_at),
+            _ => return Err(invalid()),
+        };
+        if api_key.is_empty() || !api_key.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let created_at = created_at
+            .parse::<u64>()
+            .ok()
+            .and_then(|created_at| UNIX_EPOCH.checked_add(Duration::from_millis(created_at)))
+            .ok_or_else(invalid)?;
+        Ok(Self {
+            api_key: api_key.into(),
+            location: Some(location)
+                .filter(|location| !location.is_empty())
+                .map(Into::into),
+            created_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session_id(fields: &str) -> String {
+        format!(
+            "1_{}",
+            base64::encode_config(fields, base64::URL_SAFE_NO_PAD)
+        )
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            ParsedSessionId::parse(&session_id(
+                "1~12345678~192.0.2.1~1634567890123~abcdefghij~~"
+            )),
+            Ok(ParsedSessionId {
+                api_key: "12345678".into(),
+                location: Some("192.0.2.1".into()),
+                created_at: UNIX_EPOCH + Duration::from_millis(1_634_567_890_123),
+            })
+        );
+        assert_eq!(
+            ParsedSessionId::parse(&session_id("1~12345678~~1634567890123~abcdefghijk~~"))
+                .map(|parsed| parsed.location),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for invalid in &[
+            "",
+            "1_",
+            "MX4xMjM0NTY3OH5-",
+            "x_MX4xMjM0NTY3OH5-MTYzNDU2Nzg5MDEyM35hYmNkZWZnaGlqa2xtbm9wcXJzdHV2d3h5en5-fg",
+            "1_!!!!",
+            &session_id("1~12345678~~"),
+            &session_id("1~sancho~~1634567890123~abcdefghij~~"),
+            &session_id("1~12345678~~yesterday~abcdefghij~~"),
+            &session_id("1~12345678~~99999999999999999999~abcdefghij~~"),
+            "1_MX4é",
+            "1_MX4xMjé",
+            "_MX4xMjM0NTY3OH5-",
+            "1_~~~~",
+        ] {
+            assert!(ParsedSessionId::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,8 @@
+//! Clocks of the times in these types, and of the OpenTok server SDK: those
+//! of std, except on wasm32 where they panic and those of the JavaScript
+//! host are used instead.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(target_arch = "wasm32")]
+pub use web_time::{Instant, SystemTime, UNIX_EPOCH};
@@ -0,0 +1,163 @@
+use crate::time::{SystemTime, UNIX_EPOCH};
+use crate::OpenTokError;
+
+use rustc_serialize::hex::ToHex;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::time::Duration;
+
+/// Verifier of the OpenTok callbacks relayed by a gateway signing them.
+/// The signature of a callback is the hex encoded HMAC-SHA256, keyed with
+/// a secret shared with the gateway, of its timestamp in seconds since the
+/// Unix epoch, a `.` and its body, so that neither can be altered.
+/// Callbacks whose timestamp is further than the tolerance from the local
+/// clock are rejected, so that captured callbacks cannot be replayed later.
+#[derive(Clone)]
+pub struct WebhookVerifier {
+    secret: Vec<u8>,
+    tolerance: Duration,
+}
+
+impl WebhookVerifier {
+    /// Verifies signatures made with `secret`, with a tolerance of 5
+    /// minutes.
+    pub fn new(secret: &str) -> Self {
+        Self {
+            secret: secret.as_bytes().to_vec(),
+            tolerance: Duration::from_secs(5 * 60),
+        }
+    }
+
+    /// Accepts callbacks whose timestamp is at most `tolerance` away from
+    /// the local clock.
+    pub fn with_tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Signature of a callback with `body` sent at `timestamp`, in seconds
+    /// since the Unix epoch.
+    pub fn sign(&self, timestamp: u64, body: &[u8]) -> String {
+        let mut payload = format!("{}.", timestamp).into_bytes();
+        payload.extend_from_slice(body);
+        hmac_sha256::HMAC::mac(payload, &self.secret).to_hex()
+    }
+
+    /// Checks that `signature` is the signature of the callback with `body`
+    /// sent at `timestamp`, and returns its event deserialized from `body`.
+    /// Fails with `OpenTokError::Forbidden` if the timestamp is out of
+    /// tolerance or the signature does not match, and with
+    /// `OpenTokError::BadRequest` if `body` is not a valid `T`.
+    pub fn verify<T: DeserializeOwned>(
+        &self,
+        timestamp: &str,
+        signature: &str,
+        body: &[u8],
+    ) -> Result<T, OpenTokError> {
+        self.verify_at(timestamp, signature, body, SystemTime::now())
+    }
+
+    fn verify_at<T: DeserializeOwned>(
+        &self,
+        timestamp: &str,
+        signature: &str,
+        body: &[u8],
+        now: SystemTime,
+    ) -> Result<T, OpenTokError> {
+        let secs = timestamp
+            .parse::<u64>()
+            .map_err(|_| OpenTokError::Forbidden(format!("Invalid timestamp {}", timestamp)))?;
+        // Timestamps too far in the future to be represented are as out of
+        // tolerance as any other.
+        let skew = UNIX_EPOCH
+            .checked_add(Duration::from_secs(secs))
+            .map(|sent| {
+                now.duration_since(sent)
+                    .unwrap_or_else(|error| error.duration())
+            })
+            .unwrap_or(Duration::MAX);
+        if skew > self.tolerance {
+            return Err(OpenTokError::Forbidden(format!(
+                "Timestamp {} out of tolerance",
+                timestamp
+            )));
+        }
+        let expected = self.sign(secs, body);
+        if !constant_time_eq(expected.as_bytes(), signature.as_bytes()) {
+            return Err(OpenTokError::Forbidden("Invalid signature".into()));
+        }
+        serde_json::from_slice(body)
+            .map_err(|_| OpenTokError::BadRequest(String::from_utf8_lossy(body).into_owned()))
+    }
+}
+
+impl fmt::Debug for WebhookVerifier {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("WebhookVerifier")
+            .field("tolerance", &self.tolerance)
+            .finish()
+    }
+}
+
+/// Compares `a` and `b` in a time independent of where they differ, so
+/// that signatures cannot be guessed byte by byte from response times.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct ArchiveEvent {
+        id: String,
+        status: String,
+    }
+
+    #[test]
+    fn test_webhook_verifier() {
+        let verifier = WebhookVerifier::new("quijote");
+        let body = br#"{"id":"archive","status":"available"}"#;
+        let signature = verifier.sign(1_600_000_000, body);
+        let now = UNIX_EPOCH + Duration::from_secs(1_600_000_060);
+
+        assert_eq!(
+            verifier.verify_at("1600000000", &signature, body, now),
+            Ok(ArchiveEvent {
+                id: "archive".into(),
+                status: "available".into(),
+            })
+        );
+        let spoofed = br#"{"id":"archive","status":"failed"}"#;
+        assert!(matches!(
+            verifier.verify_at::<ArchiveEvent>("1600000000", &signature, spoofed, now),
+            Err(OpenTokError::Forbidden(_))
+        ));
+        assert!(matches!(
+            verifier.verify_at::<ArchiveEvent>("1600000001", &signature, body, now),
+            Err(OpenTokError::Forbidden(_))
+        ));
+        let later = now + Duration::from_secs(10 * 60);
+        assert!(matches!(
+            verifier.verify_at::<ArchiveEvent>("1600000000", &signature, body, later),
+            Err(OpenTokError::Forbidden(_))
+        ));
+        for timestamp in &[
+            "",
+            "-1",
+            "1600000000.5",
+            "18446744073709551615",
+            "99999999999999999999",
+        ] {
+            assert!(matches!(
+                verifier.verify_at::<ArchiveEvent>(timestamp, &signature, body, now),
+                Err(OpenTokError::Forbidden(_))
+            ));
+        }
+        assert!(!format!("{:?}", verifier).contains("quijote"));
+    }
+}
@@ -0,0 +1,138 @@
+use crate::OpenTokError;
+
+use std::error::Error;
+use std::fmt;
+
+/// Number of failed items listed by the `Display` summary.
+const SUMMARY_ITEMS: usize = 3;
+
+/// Error returned by operations applied to several items at once, such as
+/// sessions or streams, when some of them failed.
+/// Keeps the error of every failed item along with the key identifying it.
+#[derive(Debug, PartialEq)]
+pub struct AggregateError {
+    errors: Vec<(String, OpenTokError)>,
+    succeeded: usize,
+}
+
+impl AggregateError {
+    /// Collects the result of each item, keyed by its identifier.
+    /// Returns the successful values if every item succeeded, and the
+    /// errors of the failed items otherwise.
+    pub fn collect<K, T, I>(results: I) -> Result<Vec<(K, T)>, AggregateError>
+    where
+        K: Into<String>,
+        I: IntoIterator<Item = (K, Result<T, OpenTokError>)>,
+    {
+        let mut values = vec![];
+        let mut errors = vec![];
+        for (key, result) in results {
+            match result {
+                Ok(value) => values.push((key, value)),
+                Err(error) => errors.push((key.into(), error)),
+            }
+        }
+        if errors.is_empty() {
+            Ok(values)
+        } else {
+            Err(AggregateError {
+                errors,
+                succeeded: values.len(),
+            })
+        }
+    }
+
+    /// Errors of the failed items, keyed by their identifier.
+    pub fn errors(&self) -> &[(String, OpenTokError)] {
+        &self.errors
+    }
+
+    pub fn into_errors(self) -> Vec<(String, OpenTokError)> {
+        self.errors
+    }
+
+    /// Number of items that succeeded.
+    pub fn succeeded(&self) -> usize {
+        self.succeeded
+    }
+
+    /// Whether every failed item failed with a transient error, so that
+    /// retrying them may succeed.
+    pub fn is_transient(&self) -> bool {
+        self.errors.iter().all(|(_, error)| error.is_transient())
+    }
+}
+
+impl fmt::Display for AggregateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} operations failed",
+            self.errors.len(),
+            self.errors.len() + self.succeeded
+        )?;
+        for (i, (key, error)) in self.errors.iter().take(SUMMARY_ITEMS).enumerate() {
+            let separator = if i == 0 { ": " } else { "; " };
+            write!(f, "{}{}: {}", separator, key, error)?;
+        }
+        if self.errors.len() > SUMMARY_ITEMS {
+            write!(f, "; and {} more", self.errors.len() - SUMMARY_ITEMS)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for AggregateError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.errors
+            .first()
+            .map(|(_, error)| error as &(dyn Error + 'static))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect() {
+        assert_eq!(
+            AggregateError::collect(vec![("a", Ok(1)), ("b", Ok(2))]),
+            Ok(vec![("a", 1), ("b", 2)])
+        );
+
+        let error = AggregateError::collect(vec![
+            ("a", Ok(1)),
+            ("b", Err(OpenTokError::Timeout)),
+            ("c", Err(OpenTokError::NotFound("c".into()))),
+        ])
+        .unwrap_err();
+        assert_eq!(error.succeeded(), 1);
+        assert_eq!(
+            error.errors(),
+            &[
+                ("b".to_string(), OpenTokError::Timeout),
+                ("c".to_string(), OpenTokError::NotFound("c".into())),
+            ]
+        );
+        assert!(!error.is_transient());
+        assert_eq!(
+            error.to_string(),
+            "2 of 3 operations failed: b: Request timed out; c: Not found c"
+        );
+    }
+
+    #[test]
+    fn test_summary() {
+        let error = AggregateError::collect::<_, (), _>(
+            (0..5).map(|i| (i.to_string(), Err(OpenTokError::Timeout))),
+        )
+        .unwrap_err();
+        assert!(error.is_transient());
+        assert_eq!(
+            error.to_string(),
+            "5 of 5 operations failed: 0: Request timed out; 1: Request timed out; \
+             2: Request timed out; and 2 more"
+        );
+    }
+}
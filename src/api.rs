@@ -0,0 +1,102 @@
+use crate::{
+    Health, OpenTok, OpenTokError, ParsedSessionId, RoomSession, SessionId, SessionOptions,
+    StreamId, StreamInfo, TokenRole,
+};
+
+use async_trait::async_trait;
+
+/// The operations of the OpenTok server SDK, as implemented by `OpenTok`.
+/// Code written against this trait rather than `OpenTok` itself can be
+/// tested without credentials nor network access, for instance with
+/// `MockOpenTok` from the `test-util` feature.
+#[async_trait]
+pub trait OpenTokApi: Send + Sync {
+    /// See `OpenTok::warm_up`.
+    async fn warm_up(&self) -> Result<(), OpenTokError>;
+
+    /// See `OpenTok::create_session`.
+    async fn create_session<'a>(
+        &self,
+        options: SessionOptions<'a>,
+    ) -> Result<SessionId, OpenTokError>;
+
+    /// See `OpenTok::create_session_for_room`.
+    async fn create_session_for_room<'a>(
+        &self,
+        room: &str,
+        options: SessionOptions<'a>,
+    ) -> Result<RoomSession, OpenTokError>;
+
+    /// See `OpenTok::validate_session_id`.
+    fn validate_session_id(&self, session_id: &str) -> Result<ParsedSessionId, OpenTokError>;
+
+    /// See `OpenTok::generate_token`.
+    fn generate_token(&self, session_id: &SessionId, role: TokenRole) -> String;
+
+    /// See `OpenTok::mint_token`.
+    async fn mint_token(
+        &self,
+        session_id: &SessionId,
+        role: TokenRole,
+    ) -> Result<String, OpenTokError>;
+
+    /// See `OpenTok::get_stream_info`.
+    async fn get_stream_info(
+        &self,
+        session_id: &SessionId,
+        stream_id: &StreamId,
+    ) -> Result<StreamInfo, OpenTokError>;
+
+    /// See `OpenTok::health`.
+    async fn health(&self) -> Health;
+}
+
+#[async_trait]
+impl OpenTokApi for OpenTok {
+    async fn warm_up(&self) -> Result<(), OpenTokError> {
+        OpenTok::warm_up(self).await
+    }
+
+    async fn create_session<'a>(
+        &self,
+        options: SessionOptions<'a>,
+    ) -> Result<SessionId, OpenTokError> {
+        OpenTok::create_session(self, options).await
+    }
+
+    async fn create_session_for_room<'a>(
+        &self,
+        room: &str,
+        options: SessionOptions<'a>,
+    ) -> Result<RoomSession, OpenTokError> {
+        OpenTok::create_session_for_room(self, room, options).await
+    }
+
+    fn validate_session_id(&self, session_id: &str) -> Result<ParsedSessionId, OpenTokError> {
+        OpenTok::validate_session_id(self, session_id)
+    }
+
+    fn generate_token(&self, session_id: &SessionId, role: TokenRole) -> String {
+        OpenTok::generate_token(self, session_id, role)
+    }
+
+    async fn mint_token(
+        &self,
+        session_id: &SessionId,
+        role: TokenRole,
+    ) -> Result<String, OpenTokError> {
+        OpenTok::mint_token(self, session_id, role).await
+    }
+
+    async fn get_stream_info(
+        &self,
+        session_id: &SessionId,
+        stream_id: &StreamId,
+    ) -> Result<StreamInfo, OpenTokError> {
+        OpenTok::get_stream_info(self, session_id, stream_id).await
+    }
+
+    async fn health(&self) -> Health {
+        OpenTok::health(self).await
+    }
+}
@@ -0,0 +1,219 @@
+//! Command line client for the common operations of the OpenTok server SDK,
+//! built with the `cli` feature.
+//!
+//! Credentials are read from the `OPENTOK_API_KEY` and `OPENTOK_API_SECRET`
+//! environment variables, or from the `api_key` and `api_secret` entries of
+//! the file given with `--config`, one `name = value` per line.
+
+use opentok_server::blocking::OpenTok;
+use opentok_server::{
+    ArchiveMode, HealthStatus, MediaMode, OpenTokError, SessionOptions, TokenRole,
+};
+
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "Usage: opentok [--config FILE] COMMAND
+
+Commands:
+    create-session [--location IP] [--media-mode relayed|routed] [--archive-mode manual|always]
+    token SESSION_ID [--role publisher|subscriber|moderator]
+    stream SESSION_ID STREAM_ID
+    health";
+
+fn main() {
+    if let Err(error) = run(env::args().skip(1).collect()) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+/// Command line command, parsed and validated before the credentials are
+/// read, so that mistakes are reported with the usage.
+enum Command {
+    CreateSession {
+        location: Option<String>,
+        media_mode: Option<String>,
+        archive_mode: Option<String>,
+    },
+    Token {
+        session_id: String,
+        role: TokenRole,
+    },
+    Stream {
+        session_id: String,
+        stream_id: String,
+    },
+    Health,
+}
+
+fn run(mut args: Vec<String>) -> Result<(), String> {
+    let config = take_option(&mut args, "--config")?;
+    let command = parse_command(args)?;
+    let opentok = match config {
+        Some(path) => {
+            let (api_key, api_secret) = read_config(&path).map_err(|error| error.to_string())?;
+            OpenTok::new(api_key, api_secret)
+        }
+        None => OpenTok::from_env().map_err(|error| error.to_string())?,
+    };
+    match command {
+        Command::CreateSession {
+            location,
+            media_mode,
+            archive_mode,
+        } => {
+            let options = session_options(
+                location.as_deref(),
+                media_mode.as_deref(),
+                archive_mode.as_deref(),
+            )?;
+            let session_id = opentok
+                .create_session(options)
+                .map_err(|error| error.to_string())?;
+            println!("{}", session_id);
+        }
+        Command::Token { session_id, role } => {
+            let session_id = opentok
+                .validate_session_id(&session_id)
+                .map(|_| session_id.as_str().into())
+                .map_err(|error| error.to_string())?;
+            println!("{}", opentok.generate_token(&session_id, role));
+        }
+        Command::Stream {
+            session_id,
+            stream_id,
+        } => {
+            let stream_info = opentok
+                .get_stream_info(&session_id.as_str().into(), &stream_id.as_str().into())
+                .map_err(|error| error.to_string())?;
+            let json =
+                serde_json::to_string_pretty(&stream_info).map_err(|error| error.to_string())?;
+            println!("{}", json);
+        }
+        Command::Health => {
+            let health = opentok.health();
+            match &health.status {
+                HealthStatus::Healthy => println!("status: healthy"),
+                HealthStatus::Unhealthy(error) => println!("status: unhealthy ({})", error),
+            }
+            println!("latency: {:?}", health.latency);
+            println!("clock skew: {:?}", health.clock_skew);
+            if !health.is_ready() {
+                process::exit(2);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_command(mut args: Vec<String>) -> Result<Command, String> {
+    if args.is_empty() {
+        return Err(USAGE.into());
+    }
+    let command = args.remove(0);
+    match command.as_str() {
+        "create-session" => {
+            let location = take_option(&mut args, "--location")?;
+            let media_mode = take_option(&mut args, "--media-mode")?;
+            let archive_mode = take_option(&mut args, "--archive-mode")?;
+            expect_args(&args, 0)?;
+            session_options(
+                location.as_deref(),
+                media_mode.as_deref(),
+                archive_mode.as_deref(),
+            )?;
+            Ok(Command::CreateSession {
+                location,
+                media_mode,
+                archive_mode,
+            })
+        }
+        "token" => {
+            let role = match take_option(&mut args, "--role")?.as_deref() {
+                None | Some("publisher") => TokenRole::Publisher,
+                Some("subscriber") => TokenRole::Subscriber,
+                Some("moderator") => TokenRole::Moderator,
+                Some(other) => return Err(format!("Unknown role {}", other)),
+            };
+            expect_args(&args, 1)?;
+            Ok(Command::Token {
+                session_id: args.remove(0),
+                role,
+            })
+        }
+        "stream" => {
+            expect_args(&args, 2)?;
+            let session_id = args.remove(0);
+            Ok(Command::Stream {
+                session_id,
+                stream_id: args.remove(0),
+            })
+        }
+        "health" => {
+            expect_args(&args, 0)?;
+            Ok(Command::Health)
+        }
+        _ => Err(USAGE.into()),
+    }
+}
+
+fn session_options<'a>(
+    location: Option<&'a str>,
+    media_mode: Option<&str>,
+    archive_mode: Option<&str>,
+) -> Result<SessionOptions<'a>, String> {
+    let mut builder = SessionOptions::builder();
+    if let Some(location) = location {
+        builder = builder.location(location);
+    }
+    match media_mode {
+        None => {}
+        Some("relayed") => builder = builder.media_mode(MediaMode::Relayed),
+        Some("routed") => builder = builder.media_mode(MediaMode::Routed),
+        Some(other) => return Err(format!("Unknown media mode {}", other)),
+    }
+    match archive_mode {
+        None => {}
+        Some("manual") => builder = builder.archive_mode(ArchiveMode::Manual),
+        Some("always") => builder = builder.archive_mode(ArchiveMode::Always),
+        Some(other) => return Err(format!("Unknown archive mode {}", other)),
+    }
+    builder.build().map_err(|error| error.to_string())
+}
+
+/// Removes `name` and the value following it from `args`, if present.
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, String> {
+    match args.iter().position(|arg| arg == name) {
+        Some(index) if index + 1 < args.len() => {
+            let value = args.remove(index + 1);
+            args.remove(index);
+            Ok(Some(value))
+        }
+        Some(_) => Err(format!("Missing value for {}", name)),
+        None => Ok(None),
+    }
+}
+
+fn expect_args(args: &[String], count: usize) -> Result<(), String> {
+    if args.len() == count {
+        Ok(())
+    } else {
+        Err(USAGE.into())
+    }
+}
+
+fn read_config(path: &str) -> Result<(String, String), OpenTokError> {
+    let config = fs::read_to_string(path)
+        .map_err(|error| OpenTokError::Config(format!("{}: {}", path, error)))?;
+    let value = |name: &str| {
+        config
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .find(|(key, _)| key.trim() == name)
+            .map(|(_, value)| value.trim().trim_matches('"').to_string())
+            .ok_or_else(|| OpenTokError::Config(format!("{}: missing {}", path, name)))
+    };
+    Ok((value("api_key")?, value("api_secret")?))
+}
@@ -0,0 +1,213 @@
+//! Synchronous facade over the OpenTok server SDK, for programs that do not
+//! want to run an async runtime, such as CLI tools and cron jobs.
+//!
+//! Every call blocks the current thread until it completes, so it must not
+//! be used from within an async context. With the `reqwest-client` feature,
+//! calls made from within a tokio runtime fail with
+//! `OpenTokError::Misuse`.
+
+use crate::{
+    ClockSkew, Health, HealthStatus, OpenTokError, ParsedSessionId, RoomSession, SessionId,
+    SessionOptions, StreamId, StreamInfo, TokenRole,
+};
+
+use futures::Future;
+use std::time::Duration;
+
+/// Blocking version of `opentok_server::OpenTok`.
+pub struct OpenTok {
+    inner: crate::OpenTok,
+    /// Runtime the calls are run on, as reqwest requires one, or the reason
+    /// it could not be created, returned by every call.
+    #[cfg(feature = "reqwest-client")]
+    runtime: Result<Runtime, OpenTokError>,
+}
+
+/// Current thread tokio runtime. Blocking on a tokio runtime or dropping it
+/// from within an async context panics, so calls made from one fail
+/// instead, and the runtime is shut down in the background when dropped.
+#[cfg(feature = "reqwest-client")]
+struct Runtime(Option<tokio::runtime::Runtime>);
+
+#[cfg(feature = "reqwest-client")]
+impl Runtime {
+    fn new() -> Result<Self, OpenTokError> {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map(|runtime| Self(Some(runtime)))
+            .map_err(|error| OpenTokError::ConnectionError(error.to_string()))
+    }
+
+    fn block_on<T>(
+        &self,
+        future: impl Future<Output = Result<T, OpenTokError>>,
+    ) -> Result<T, OpenTokError> {
+        match (&self.0, tokio::runtime::Handle::try_current()) {
+            (Some(runtime), Err(_)) => runtime.block_on(future),
+            _ => Err(OpenTokError::Misuse(
+                "Blocking call made from within an async context".into(),
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "reqwest-client")]
+impl Drop for Runtime {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.0.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+impl From<crate::OpenTok> for OpenTok {
+    /// Wraps an asynchronous instance, for instance one configured with
+    /// `OpenTokBuilder`.
+    fn from(inner: crate::OpenTok) -> Self {
+        Self {
+            inner,
+            #[cfg(feature = "reqwest-client")]
+            runtime: Runtime::new(),
+        }
+    }
+}
+
+impl OpenTok {
+    /// Create a new instance of OpenTok. Requires an OpenTok API key and
+    /// the API secret for your TokBox account. Do not publicly share your
+    /// API secret.
+    pub fn new(api_key: String, api_secret: String) -> Self {
+        crate::OpenTok::new(api_key, api_secret).into()
+    }
+
+    /// See `opentok_server::OpenTok::from_env`.
+    pub fn from_env() -> Result<Self, OpenTokError> {
+        crate::OpenTok::from_env().map(Into::into)
+    }
+
+    fn block_on<T>(
+        &self,
+        future: impl Future<Output = Result<T, OpenTokError>>,
+    ) -> Result<T, OpenTokError> {
+        #[cfg(feature = "reqwest-client")]
+        {
+            match &self.runtime {
+                Ok(runtime) => runtime.block_on(future),
+                Err(error) => Err(error.clone()),
+            }
+        }
+        #[cfg(not(feature = "reqwest-client"))]
+        {
+            futures::executor::block_on(future)
+        }
+    }
+
+    /// See `opentok_server::OpenTok::warm_up`.
+    pub fn warm_up(&self) -> Result<(), OpenTokError> {
+        self.block_on(self.inner.warm_up())
+    }
+
+    /// Creates a new OpenTok session.
+    /// On success, a session ID is provided.
+    pub fn create_session(&self, options: SessionOptions) -> Result<SessionId, OpenTokError> {
+        self.block_on(self.inner.create_session(options))
+    }
+
+    /// See `opentok_server::OpenTok::create_session_for_room`.
+    pub fn create_session_for_room(
+        &self,
+        room: &str,
+        options: SessionOptions,
+    ) -> Result<RoomSession, OpenTokError> {
+        self.block_on(self.inner.create_session_for_room(room, options))
+    }
+
+    /// See `opentok_server::OpenTok::validate_session_id`.
+    pub fn validate_session_id(&self, session_id: &str) -> Result<ParsedSessionId, OpenTokError> {
+        self.inner.validate_session_id(session_id)
+    }
+
+    pub fn generate_token(&self, session_id: &SessionId, role: TokenRole) -> String {
+        self.inner.generate_token(session_id, role)
+    }
+
+    pub fn mint_token(
+        &self,
+        session_id: &SessionId,
+        role: TokenRole,
+    ) -> Result<String, OpenTokError> {
+        self.block_on(self.inner.mint_token(session_id, role))
+    }
+
+    pub fn get_stream_info(
+        &self,
+        session_id: &SessionId,
+        stream_id: &StreamId,
+    ) -> Result<StreamInfo, OpenTokError> {
+        self.block_on(self.inner.get_stream_info(session_id, stream_id))
+    }
+
+    /// See `opentok_server::OpenTok::health`.
+    pub fn health(&self) -> Health {
+        self.block_on(async { Ok(self.inner.health().await) })
+            .unwrap_or_else(|error| Health {
+                status: HealthStatus::Unhealthy(error),
+                latency: Duration::from_secs(0),
+                clock_skew: ClockSkew::Unknown,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    #[test]
+    fn test_create_session_invalid_credentials() {
+        let opentok = OpenTok::new("sancho".into(), "quijote".into());
+        assert!(opentok.create_session(SessionOptions::default()).is_err());
+    }
+
+    #[cfg(feature = "reqwest-client")]
+    #[test]
+    fn test_reqwest_client() {
+        use crate::{PoolConfig, Proxy, ReqwestClient};
+
+        let proxy = Proxy::new("http://127.0.0.1:1").unwrap();
+        let client = ReqwestClient::configured(None, Some(&proxy), &PoolConfig::default()).unwrap();
+        let opentok: OpenTok = crate::OpenTok::builder("sancho".into(), "quijote".into())
+            .with_http_client(client)
+            .build()
+            .into();
+        assert!(matches!(
+            opentok.create_session(SessionOptions::default()),
+            Err(OpenTokError::ConnectionError(_))
+        ));
+    }
+
+    #[cfg(feature = "reqwest-client")]
+    #[tokio::test]
+    async fn test_async_context() {
+        let opentok = OpenTok::new("sancho".into(), "quijote".into());
+        assert!(matches!(
+            opentok.create_session(SessionOptions::default()),
+            Err(OpenTokError::Misuse(_))
+        ));
+        assert!(!opentok.health().is_ready());
+        drop(opentok);
+    }
+
+    #[test]
+    fn test_create_session() {
+        let api_key = env::var("OPENTOK_KEY").unwrap();
+        let api_secret = env::var("OPENTOK_SECRET").unwrap();
+        let opentok = OpenTok::new(api_key, api_secret);
+        let session_id = opentok.create_session(SessionOptions::default()).unwrap();
+        assert!(!session_id.as_str().is_empty());
+        let token = opentok.generate_token(&session_id, TokenRole::Publisher);
+        assert!(!token.is_empty());
+    }
+}
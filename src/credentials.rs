@@ -0,0 +1,140 @@
+use crate::OpenTokError;
+
+use std::env;
+use std::fmt;
+use std::sync::{PoisonError, RwLock};
+
+const API_KEY_VAR: &str = "OPENTOK_API_KEY";
+const API_SECRET_VAR: &str = "OPENTOK_API_SECRET";
+
+/// API key and secret of an OpenTok project.
+#[derive(Clone, PartialEq)]
+pub struct Credentials {
+    pub api_key: String,
+    pub api_secret: String,
+}
+
+impl Credentials {
+    pub fn new(api_key: String, api_secret: String) -> Self {
+        Self {
+            api_key,
+            api_secret,
+        }
+    }
+
+    /// Reads the credentials from the `OPENTOK_API_KEY` and
+    /// `OPENTOK_API_SECRET` environment variables.
+    /// Fails with `OpenTokError::Config` if either is not set.
+    pub fn from_env() -> Result<Self, OpenTokError> {
+        let var = |name| {
+            env::var(name)
+                .map_err(|_| OpenTokError::Config(format!("Missing environment variable {}", name)))
+        };
+        Ok(Self::new(var(API_KEY_VAR)?, var(API_SECRET_VAR)?))
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("Credentials")
+            .field("api_key", &self.api_key)
+            .finish()
+    }
+}
+
+/// Source of the credentials of an `OpenTok` instance, consulted for every
+/// request and token, so that they can be rotated without rebuilding it.
+/// Called on the request path, so implementations backed by a secret
+/// manager should refresh their credentials in the background rather than
+/// fetching them on each call. Requests rejected with a 401 status code
+/// are sent once more with the credentials it then returns.
+pub trait CredentialsProvider: Send + Sync {
+    fn credentials(&self) -> Credentials;
+}
+
+impl CredentialsProvider for Credentials {
+    fn credentials(&self) -> Credentials {
+        self.clone()
+    }
+}
+
+/// `CredentialsProvider` holding credentials in memory, replaced with
+/// `set`, for instance from a task watching a secret manager.
+#[derive(Debug)]
+pub struct InMemoryCredentials {
+    credentials: RwLock<Credentials>,
+}
+
+impl InMemoryCredentials {
+    pub fn new(credentials: Credentials) -> Self {
+        Self {
+            credentials: RwLock::new(credentials),
+        }
+    }
+
+    /// Replaces the credentials used from the next request on.
+    pub fn set(&self, credentials: Credentials) {
+        *self
+            .credentials
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = credentials;
+    }
+}
+
+impl CredentialsProvider for InMemoryCredentials {
+    fn credentials(&self) -> Credentials {
+        self.credentials
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::CannedClient;
+    use crate::{HttpRequest, OpenTok, SessionOptions};
+
+    use futures::executor::block_on;
+    use std::sync::Arc;
+
+    /// Issuer of the JWT authenticating `request`.
+    fn issuer(request: &HttpRequest) -> String {
+        let (_, jwt) = request
+            .headers
+            .iter()
+            .find(|(name, _)| name == "X-OPENTOK-AUTH")
+            .unwrap();
+        let claims = jwt.split('.').nth(1).unwrap();
+        let claims = base64::decode_config(claims, base64::URL_SAFE_NO_PAD).unwrap();
+        let claims: serde_json::Value = serde_json::from_slice(&claims).unwrap();
+        claims["iss"].as_str().unwrap().into()
+    }
+
+    #[test]
+    fn test_credentials_debug_redacts_secret() {
+        let credentials = Credentials::new("sancho".into(), "quijote".into());
+        assert!(!format!("{:?}", credentials).contains("quijote"));
+    }
+
+    #[test]
+    fn test_rotated_credentials() {
+        let client = CannedClient::new(200, r#"[{"session_id":"1_MX4"}]"#);
+        let provider = Arc::new(InMemoryCredentials::new(Credentials::new(
+            "sancho".into(),
+            "quijote".into(),
+        )));
+        let opentok = OpenTok::builder_with_credentials(provider.clone())
+            .with_http_client(client.clone())
+            .build();
+
+        block_on(opentok.create_session(SessionOptions::default())).unwrap();
+        provider.set(Credentials::new("rocinante".into(), "dulcinea".into()));
+        block_on(opentok.create_session(SessionOptions::default())).unwrap();
+
+        let issuers: Vec<_> = client.requests().iter().map(issuer).collect();
+        assert_eq!(issuers, vec!["sancho".to_string(), "rocinante".to_string()]);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::version::API_VERSION;
+use crate::OpenTokError;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+static SERVER_URL: &str = "https://api.opentok.com";
+#[cfg(feature = "insights")]
+static INSIGHTS_URL: &str = "https://insights.opentok.com/graphql";
+
+/// Characters left as is in path segments and query values: RFC 3986
+/// unreserved characters. Everything else is percent-encoded.
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// URL of an OpenTok REST API endpoint.
+/// Path segments and query values are percent-encoded as they are added,
+/// so IDs received from clients can't alter the path of the request.
+/// Empty and dot segments, which would still do so, make the endpoint invalid,
+/// as do segments added after the query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Endpoint {
+    url: String,
+    has_query: bool,
+    /// Why the endpoint is invalid, reported by `Endpoint::url`.
+    invalid: Option<String>,
+}
+
+impl Endpoint {
+    /// Root of the OpenTok API, for endpoints not scoped to a project.
+    pub fn root() -> Self {
+        Self {
+            url: SERVER_URL.into(),
+            has_query: false,
+            invalid: None,
+        }
+    }
+
+    /// GraphQL endpoint of the OpenTok Insights API.
+    #[cfg(feature = "insights")]
+    pub fn insights() -> Self {
+        Self {
+            url: INSIGHTS_URL.into(),
+            has_query: false,
+            invalid: None,
+        }
+    }
+
+    /// Root of the endpoints scoped to the project with the given API key.
+    pub fn project(api_key: &str) -> Self {
+        let url = format!(
+            "{}/{}/project/{}",
+            SERVER_URL,
+            API_VERSION,
+            utf8_percent_encode(api_key, COMPONENT)
+        );
+        Self {
+            url,
+            has_query: false,
+            invalid: None,
+        }
+    }
+
+    /// Appends a path segment.
+    pub fn segment(mut self, segment: &str) -> Self {
+        if self.invalid.is_none() {
+            if self.has_query {
+                self.invalid = Some(format!("Path segment {:?} added after the query", segment));
+            } else if matches!(segment, "" | "." | "..") {
+                self.invalid = Some(format!("Invalid path segment {:?}", segment));
+            }
+        }
+        if self.has_query {
+            return self;
+        }
+        self.url.push('/');
+        self.url.extend(utf8_percent_encode(segment, COMPONENT));
+        self
+    }
+
+    /// Appends a query parameter.
+    pub fn query(mut self, key: &str, value: &str) -> Self {
+        self.url.push(if self.has_query { '&' } else { '?' });
+        self.url.extend(utf8_percent_encode(key, COMPONENT));
+        self.url.push('=');
+        self.url.extend(utf8_percent_encode(value, COMPONENT));
+        self.has_query = true;
+        self
+    }
+
+    /// The URL of the endpoint, unless it is invalid.
+    pub fn url(&self) -> Result<&str, OpenTokError> {
+        match &self.invalid {
+            Some(reason) => Err(OpenTokError::BadRequest(reason.clone())),
+            None => Ok(&self.url),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint() {
+        assert_eq!(
+            Endpoint::root().segment("session").segment("create").url(),
+            Ok("https://api.opentok.com/session/create")
+        );
+        assert_eq!(
+            Endpoint::project("1234")
+                .segment("session")
+                .segment("1_MX4xMjM0fn4-fg")
+                .url(),
+            Ok("https://api.opentok.com/v2/project/1234/session/1_MX4xMjM0fn4-fg")
+        );
+        assert_eq!(
+            Endpoint::project("1234")
+                .segment("archive")
+                .query("offset", "0")
+                .query("count", "1")
+                .url(),
+            Ok("https://api.opentok.com/v2/project/1234/archive?offset=0&count=1")
+        );
+    }
+
+    #[test]
+    fn test_endpoint_escaping() {
+        assert_eq!(
+            Endpoint::project("1234")
+                .segment("session")
+                .segment("../../session/x?y=z#w")
+                .segment("stream")
+                .segment("a/b c%")
+                .url(),
+            Ok("https://api.opentok.com/v2/project/1234/session/..%2F..%2Fsession%2Fx%3Fy%3Dz%23w/stream/a%2Fb%20c%25")
+        );
+        assert_eq!(
+            Endpoint::project("12/34").query("a&b", "c=d").url(),
+            Ok("https://api.opentok.com/v2/project/12%2F34?a%26b=c%3Dd")
+        );
+        for segment in &["", ".", ".."] {
+            assert!(matches!(
+                Endpoint::project("1234")
+                    .segment("session")
+                    .segment(segment)
+                    .segment("stream")
+                    .url(),
+                Err(OpenTokError::BadRequest(_))
+            ));
+        }
+        assert!(matches!(
+            Endpoint::project("1234")
+                .segment("archive")
+                .query("count", "1")
+                .segment("stream")
+                .url(),
+            Err(OpenTokError::BadRequest(_))
+        ));
+    }
+}
@@ -0,0 +1,172 @@
+use crate::{HttpMethod, HttpRequest, HttpResponse, Middleware, Next, OpenTokError};
+
+use async_trait::async_trait;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, PoisonError};
+
+const ETAG: &str = "ETag";
+const IF_NONE_MATCH: &str = "If-None-Match";
+
+#[derive(Debug, Default)]
+struct Entries {
+    responses: HashMap<String, (String, HttpResponse)>,
+    /// URLs in insertion order, to evict the oldest entries first.
+    order: VecDeque<String>,
+}
+
+/// Middleware caching the responses of GET requests carrying an `ETag`
+/// header, and revalidating them with `If-None-Match` on the following
+/// requests to the same URL. When the API answers `304 Not Modified`, the
+/// cached response is returned instead, so that frequent pollers do not
+/// download the same payloads over and over.
+/// Add it with `OpenTokBuilder::with_middleware`.
+#[derive(Debug)]
+pub struct EtagCache {
+    entries: Mutex<Entries>,
+    max_entries: usize,
+}
+
+impl EtagCache {
+    /// Cache remembering the responses of at most `max_entries` URLs, evicting
+    /// the oldest ones first.
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: Mutex::new(Entries::default()),
+            max_entries,
+        }
+    }
+
+    fn get(&self, url: &str) -> Option<(String, HttpResponse)> {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .responses
+            .get(url)
+            .cloned()
+    }
+
+    fn put(&self, url: &str, etag: String, response: HttpResponse) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        if entries
+            .responses
+            .insert(url.into(), (etag, response))
+            .is_none()
+        {
+            entries.order.push_back(url.into());
+            while entries.order.len() > self.max_entries {
+                if let Some(oldest) = entries.order.pop_front() {
+                    entries.responses.remove(&oldest);
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for EtagCache {
+    async fn handle(
+        &self,
+        mut request: HttpRequest,
+        next: Next<'_>,
+    ) -> Result<HttpResponse, OpenTokError> {
+        if request.method != HttpMethod::Get {
+            return next.run(request).await;
+        }
+        let url = request.url.clone();
+        let cached = self.get(&url);
+        if let Some((etag, _)) = &cached {
+            request.headers.push((IF_NONE_MATCH.into(), etag.clone()));
+        }
+        let response = next.run(request).await?;
+        match (response.status, cached) {
+            (304, Some((_, cached))) => Ok(cached),
+            (200..=299, _) => {
+                if let Some(etag) = response.header(ETAG) {
+                    self.put(&url, etag.into(), response.clone());
+                }
+                Ok(response)
+            }
+            _ => Ok(response),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::MiddlewareClient;
+    use crate::HttpClient;
+
+    use futures::executor::block_on;
+    use std::sync::Arc;
+
+    /// `HttpClient` serving a resource with a fixed ETag, honoring
+    /// `If-None-Match`.
+    #[derive(Default)]
+    struct EtagServer {
+        requests: Mutex<Vec<HttpRequest>>,
+    }
+
+    #[async_trait]
+    impl HttpClient for EtagServer {
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse, OpenTokError> {
+            let not_modified = request
+                .headers
+                .iter()
+                .any(|(name, value)| name == IF_NONE_MATCH && value == "\"v1\"");
+            self.requests.lock().unwrap().push(request);
+            Ok(if not_modified {
+                HttpResponse {
+                    status: 304,
+                    headers: vec![],
+                    body: vec![],
+                }
+            } else {
+                HttpResponse {
+                    status: 200,
+                    headers: vec![(ETAG.into(), "\"v1\"".into())],
+                    body: b"{}".to_vec(),
+                }
+            })
+        }
+    }
+
+    fn get(url: &str) -> HttpRequest {
+        HttpRequest {
+            method: HttpMethod::Get,
+            url: url.into(),
+            headers: vec![],
+            body: None,
+        }
+    }
+
+    #[test]
+    fn test_etag_cache() {
+        let server = Arc::new(EtagServer::default());
+        let client = MiddlewareClient::new(server.clone(), vec![Arc::new(EtagCache::new(1))]);
+
+        let first = block_on(client.send(get("https://api.opentok.com/a"))).unwrap();
+        let second = block_on(client.send(get("https://api.opentok.com/a"))).unwrap();
+        assert_eq!(second.status, 200);
+        assert_eq!(second.body, first.body);
+
+        // Caching another URL evicts the first one.
+        block_on(client.send(get("https://api.opentok.com/b"))).unwrap();
+        block_on(client.send(get("https://api.opentok.com/a"))).unwrap();
+
+        let requests = server.requests.lock().unwrap();
+        let revalidated: Vec<_> = requests
+            .iter()
+            .map(|request| {
+                request
+                    .headers
+                    .iter()
+                    .any(|(name, _)| name == IF_NONE_MATCH)
+            })
+            .collect();
+        assert_eq!(revalidated, vec![false, true, false, false]);
+    }
+}
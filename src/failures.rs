@@ -0,0 +1,215 @@
+use crate::http_client::AUTH_HEADER;
+use crate::time::SystemTime;
+use crate::{HttpRequest, HttpResponse, OpenTokError, TimeSource};
+
+use rand::Rng;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, PoisonError};
+
+const REDACTED: &str = "<redacted>";
+
+/// Configuration of the failed requests kept for `OpenTok::recent_failures`,
+/// set with `OpenTokBuilder::with_failure_sampling`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FailureSampling {
+    /// Number of failed requests kept, the oldest being dropped first.
+    pub capacity: usize,
+    /// Fraction of the failed requests kept, between 0 and 1. Rates out of
+    /// that range are clamped to it, and NaN keeps none.
+    pub rate: f64,
+}
+
+impl FailureSampling {
+    /// Keeps every failed request, up to the last `capacity` ones.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            rate: 1.0,
+        }
+    }
+
+    /// Keeps only a `rate` fraction of the failed requests, picked at
+    /// random.
+    pub fn with_rate(mut self, rate: f64) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// `rate` as a probability `gen_bool` accepts.
+    fn probability(&self) -> f64 {
+        if self.rate.is_nan() {
+            0.0
+        } else {
+            self.rate.clamp(0.0, 1.0)
+        }
+    }
+}
+
+/// Attempt of a request that failed, either because it could not be
+/// completed or because its response had a non 2xx status code.
+#[derive(Clone, Debug)]
+pub struct FailedRequest {
+    pub at: SystemTime,
+    /// Attempt number, starting at 1, of a request that may have been
+    /// retried.
+    pub attempt: u32,
+    /// The request sent, with the value of its JWT header and its body,
+    /// which may carry secrets, redacted.
+    pub request: HttpRequest,
+    pub response: Result<HttpResponse, OpenTokError>,
+}
+
+/// Ring buffer of the sampled failed requests, timestamped with
+/// `time_source`.
+pub(crate) struct FailureLog {
+    sampling: FailureSampling,
+    time_source: Arc<dyn TimeSource>,
+    failures: Mutex<VecDeque<FailedRequest>>,
+}
+
+impl FailureLog {
+    pub fn new(sampling: FailureSampling, time_source: Arc<dyn TimeSource>) -> Self {
+        Self {
+            // Grown as failures are recorded rather than allocated up front,
+            // whatever the capacity.
+            failures: Mutex::new(VecDeque::new()),
+            sampling,
+            time_source,
+        }
+    }
+
+    /// Keeps the outcome of an attempt of `request` if it failed and gets
+    /// sampled.
+    pub fn record(
+        &self,
+        attempt: u32,
+        request: &HttpRequest,
+        response: &Result<HttpResponse, OpenTokError>,
+    ) {
+        let failed = match response {
+            Ok(response) => !(200..=299).contains(&response.status),
+            Err(_) => true,
+        };
+        if !failed
+            || self.sampling.capacity == 0
+            || !rand::thread_rng().gen_bool(self.sampling.probability())
+        {
+            return;
+        }
+        let mut request = request.clone();
+        for (name, value) in request.headers.iter_mut() {
+            if name.eq_ignore_ascii_case(AUTH_HEADER) {
+                *value = REDACTED.into();
+            }
+        }
+        if let Some(body) = request.body.as_mut() {
+            *body = REDACTED.as_bytes().to_vec();
+        }
+        let mut failures = self.failures.lock().unwrap_or_else(PoisonError::into_inner);
+        if failures.len() == self.sampling.capacity {
+            failures.pop_front();
+        }
+        failures.push_back(FailedRequest {
+            at: self.time_source.now(),
+            attempt,
+            request,
+            response: response.clone(),
+        });
+    }
+
+    /// The failed requests kept, oldest first.
+    pub fn failures(&self) -> Vec<FailedRequest> {
+        self.failures
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::FixedTime;
+    use crate::{HttpMethod, SystemTimeSource};
+
+    use std::time::Duration;
+
+    fn request(url: &str) -> HttpRequest {
+        HttpRequest {
+            method: HttpMethod::Get,
+            url: url.into(),
+            headers: vec![(AUTH_HEADER.into(), "jwt".into())],
+            body: None,
+        }
+    }
+
+    fn response(status: u16) -> Result<HttpResponse, OpenTokError> {
+        Ok(HttpResponse {
+            status,
+            headers: vec![],
+            body: vec![],
+        })
+    }
+
+    #[test]
+    fn test_failure_log() {
+        let time = crate::time::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let log = FailureLog::new(FailureSampling::new(2), Arc::new(FixedTime(time)));
+        log.record(1, &request("https://a"), &response(500));
+        log.record(1, &request("https://b"), &response(200));
+        log.record(1, &request("https://c"), &Err(OpenTokError::Timeout));
+        let mut with_body = request("https://d");
+        with_body.body = Some(br#"{"secret":"quijote"}"#.to_vec());
+        log.record(2, &with_body, &response(404));
+
+        let failures = log.failures();
+        let urls: Vec<_> = failures
+            .iter()
+            .map(|failure| &failure.request.url)
+            .collect();
+        assert_eq!(urls, vec!["https://c", "https://d"]);
+        assert_eq!(
+            failures[0].response.as_ref().err(),
+            Some(&OpenTokError::Timeout)
+        );
+        assert_eq!(failures[0].at, time);
+        assert_eq!(failures[1].attempt, 2);
+        assert_eq!(failures[1].request.headers[0].1, "<redacted>");
+        assert_eq!(failures[1].request.body, Some(b"<redacted>".to_vec()));
+        assert_eq!(failures[0].request.body, None);
+
+        let log = FailureLog::new(
+            FailureSampling::new(2).with_rate(0.0),
+            Arc::new(SystemTimeSource),
+        );
+        log.record(1, &request("https://a"), &response(500));
+        assert!(log.failures().is_empty());
+    }
+
+    #[test]
+    fn test_failure_log_large_capacity() {
+        let log = FailureLog::new(FailureSampling::new(usize::MAX), Arc::new(SystemTimeSource));
+        log.record(1, &request("https://a"), &response(500));
+        assert_eq!(log.failures().len(), 1);
+    }
+
+    #[test]
+    fn test_failure_sampling_out_of_range() {
+        for (rate, kept) in [
+            (f64::NAN, 0),
+            (f64::NEG_INFINITY, 0),
+            (-1.0, 0),
+            (2.0, 1),
+            (f64::INFINITY, 1),
+        ] {
+            let log = FailureLog::new(
+                FailureSampling::new(2).with_rate(rate),
+                Arc::new(SystemTimeSource),
+            );
+            log.record(1, &request("https://a"), &response(500));
+            assert_eq!(log.failures().len(), kept);
+        }
+    }
+}
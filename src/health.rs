@@ -0,0 +1,95 @@
+use crate::http_client::JWT_LIFETIME_SECS;
+use crate::time::SystemTime;
+use crate::OpenTokError;
+
+use std::time::Duration;
+
+/// How far ahead of the server clock, in seconds, the local clock can be
+/// before the `iat` claim of our JWTs is considered to be in the future.
+const MAX_CLOCK_AHEAD_SECS: i64 = 5;
+
+/// Whether the configured OpenTok API endpoint accepted our request.
+#[derive(Debug, PartialEq)]
+pub enum HealthStatus {
+    /// The endpoint answered an authenticated request successfully.
+    Healthy,
+    /// The endpoint could not be reached or rejected the request.
+    Unhealthy(OpenTokError),
+}
+
+/// Difference between the local clock and the OpenTok server clock, as
+/// reported by the `Date` header of the health check response.
+/// Offsets are given in seconds, positive when the local clock is ahead.
+#[derive(Debug, PartialEq)]
+pub enum ClockSkew {
+    /// The server did not report its time.
+    Unknown,
+    /// The local clock is close enough to the server clock for JWTs to be accepted.
+    InSync(i64),
+    /// The local clock is ahead of the server clock, so JWTs may be rejected
+    /// as issued in the future.
+    Ahead(i64),
+    /// The local clock is behind the server clock, so JWTs may already be
+    /// expired by the time they reach the server.
+    Behind(i64),
+}
+
+impl ClockSkew {
+    pub(crate) fn new(local: SystemTime, server: SystemTime) -> Self {
+        let offset = match local.duration_since(server) {
+            Ok(ahead) => ahead.as_secs() as i64,
+            Err(behind) => -(behind.duration().as_secs() as i64),
+        };
+        if offset > MAX_CLOCK_AHEAD_SECS {
+            ClockSkew::Ahead(offset)
+        } else if -offset >= JWT_LIFETIME_SECS as i64 {
+            ClockSkew::Behind(offset)
+        } else {
+            ClockSkew::InSync(offset)
+        }
+    }
+}
+
+/// Result of a health check against the OpenTok API, suitable for backing
+/// readiness probes of services that depend on OpenTok.
+#[derive(Debug)]
+pub struct Health {
+    /// Status of the configured API endpoint.
+    pub status: HealthStatus,
+    /// Round trip time of the health check request.
+    pub latency: Duration,
+    /// Skew between the local and the server clocks.
+    pub clock_skew: ClockSkew,
+}
+
+impl Health {
+    /// Whether the API endpoint is healthy and the local clock is in sync
+    /// enough with it for authenticated requests to succeed.
+    pub fn is_ready(&self) -> bool {
+        self.status == HealthStatus::Healthy
+            && matches!(self.clock_skew, ClockSkew::InSync(_) | ClockSkew::Unknown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_skew() {
+        let server = SystemTime::now();
+        assert_eq!(ClockSkew::new(server, server), ClockSkew::InSync(0));
+        assert_eq!(
+            ClockSkew::new(server + Duration::from_secs(60), server),
+            ClockSkew::Ahead(60)
+        );
+        assert_eq!(
+            ClockSkew::new(server - Duration::from_secs(60), server),
+            ClockSkew::InSync(-60)
+        );
+        assert_eq!(
+            ClockSkew::new(server - Duration::from_secs(600), server),
+            ClockSkew::Behind(-600)
+        );
+    }
+}
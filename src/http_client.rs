@@ -1,13 +1,39 @@
-use crate::OpenTokError;
+use crate::endpoint::Endpoint;
+use crate::failures::{FailedRequest, FailureLog};
+use crate::rate_limit::RateLimiter;
+use crate::retry::RetryPolicy;
+use crate::sources::{unix_secs, NonceSource, RandomNonceSource, SystemTimeSource, TimeSource};
+use crate::time::{Instant, SystemTime};
+use crate::timeout::{timeout, Timeouts};
+use crate::{Credentials, CredentialsProvider, OpenTokError, PoolConfig, Proxy, Timer};
 
+use async_trait::async_trait;
 use jsonwebtoken::{encode, EncodingKey, Header};
-use rand::Rng;
-use serde::Serialize;
-use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tracing::{debug, debug_span, warn, Instrument};
 
-static AUTH_HEADER: &str = "X-OPENTOK-AUTH";
+#[cfg(feature = "reqwest-client")]
+mod reqwest_client;
+#[cfg(feature = "surf-client")]
+mod surf_client;
+
+#[cfg(feature = "reqwest-client")]
+pub use reqwest_client::ReqwestClient;
+#[cfg(feature = "surf-client")]
+pub use surf_client::SurfClient;
+
+pub(crate) static AUTH_HEADER: &str = "X-OPENTOK-AUTH";
 static ACCEPT: &str = "Accept";
+static CONTENT_TYPE: &str = "Content-Type";
+static FORM: &str = "application/x-www-form-urlencoded";
 static JSON: &str = "application/json";
+static RETRY_AFTER: &str = "Retry-After";
+
+/// Lifetime in seconds of the JWT sent with every request.
+pub const JWT_LIFETIME_SECS: u64 = 3 * 60;
 
 #[derive(Debug, Serialize)]
 struct Claims<'a> {
@@ -19,72 +45,830 @@ struct Claims<'a> {
 }
 
 impl<'a> Claims<'a> {
-    fn new(api_key: &'a str) -> Self {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards, Doc!")
-            .as_secs();
-        let mut rng = rand::thread_rng();
+    fn new(api_key: &'a str, now: u64, jti: u64) -> Self {
         Self {
             iss: api_key,
             ist: "project",
             iat: now,
-            exp: now + (3 * 60),
-            jti: rng.gen::<u64>(),
+            exp: now + JWT_LIFETIME_SECS,
+            jti,
         }
     }
 }
 
-fn auth_header(api_key: &str, api_secret: &str) -> Result<String, OpenTokError> {
-    let claims = Claims::new(api_key);
+/// Signs a new JWT, bypassing the cache of the client. Only measured by the
+/// benchmarks, as the cost of a cache miss.
+#[cfg(feature = "benchmarks")]
+pub fn auth_header(api_key: &str, api_secret: &str) -> Result<String, OpenTokError> {
+    let now = unix_secs(SystemTimeSource.now());
+    sign(
+        &Claims::new(api_key, now, RandomNonceSource.nonce()),
+        api_secret,
+    )
+}
+
+fn sign(claims: &Claims, api_secret: &str) -> Result<String, OpenTokError> {
     encode(
         &Header::default(),
-        &claims,
+        claims,
         &EncodingKey::from_secret(api_secret.as_ref()),
     )
     .map_err(|_| OpenTokError::EncodingError)
 }
 
-async fn from_surf_response(response: surf::Result) -> Result<surf::Response, OpenTokError> {
-    match response {
-        Ok(mut response) => match response.status().into() {
-            200..=299 => Ok(response),
-            _ => {
-                let body = response
-                    .body_string()
-                    .await
-                    .map_err(|_| OpenTokError::UnexpectedResponse(format!("{:?}", response)))?;
-                let error = surf::Error::from_str(response.status(), body);
-                Err(error.into())
+/// How long before its expiry a cached JWT gets replaced, so that it does
+/// not expire while a request using it is in flight or being retried.
+const JWT_REFRESH_MARGIN_SECS: u64 = 30;
+
+struct CachedJwt {
+    api_key: String,
+    api_secret: String,
+    jwt: String,
+    expires_at: u64,
+}
+
+/// JWT reused across requests until it gets close to expiring, sparing the
+/// signature of a new one for every request.
+struct JwtCache {
+    cached: Mutex<Option<CachedJwt>>,
+    time_source: Arc<dyn TimeSource>,
+    nonce_source: Arc<dyn NonceSource>,
+}
+
+impl Default for JwtCache {
+    fn default() -> Self {
+        Self::new(Arc::new(SystemTimeSource), Arc::new(RandomNonceSource))
+    }
+}
+
+impl JwtCache {
+    fn new(time_source: Arc<dyn TimeSource>, nonce_source: Arc<dyn NonceSource>) -> Self {
+        Self {
+            cached: Mutex::new(None),
+            time_source,
+            nonce_source,
+        }
+    }
+
+    fn get(&self, api_key: &str, api_secret: &str) -> Result<String, OpenTokError> {
+        // Holding the lock while signing keeps concurrent requests from
+        // all signing a replacement at once.
+        let mut cached = self.cached.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = unix_secs(self.time_source.now());
+        match &*cached {
+            Some(jwt)
+                if jwt.api_key == api_key
+                    && jwt.api_secret == api_secret
+                    && now + JWT_REFRESH_MARGIN_SECS < jwt.expires_at =>
+            {
+                return Ok(jwt.jwt.clone())
             }
+            _ => {}
+        }
+        let claims = Claims::new(api_key, now, self.nonce_source.nonce());
+        let jwt = sign(&claims, api_secret)?;
+        *cached = Some(CachedJwt {
+            api_key: api_key.into(),
+            api_secret: api_secret.into(),
+            jwt: jwt.clone(),
+            expires_at: claims.exp,
+        });
+        Ok(jwt)
+    }
+
+    /// Drops the cached JWT, so that the next request signs a new one.
+    fn invalidate(&self) {
+        *self.cached.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
+
+/// HTTP method of an `HttpRequest`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HttpMethod {
+    Get,
+    Head,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+/// Request handed to an `HttpClient`.
+/// Its `Debug` output leaves out the JWT and the body, so that requests can
+/// be logged safely.
+#[derive(Clone)]
+pub struct HttpRequest {
+    pub method: HttpMethod,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl fmt::Debug for HttpRequest {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let headers: Vec<_> = self
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                if name.eq_ignore_ascii_case(AUTH_HEADER) {
+                    (name.as_str(), "<redacted>")
+                } else {
+                    (name.as_str(), value.as_str())
+                }
+            })
+            .collect();
+        formatter
+            .debug_struct("HttpRequest")
+            .field("method", &self.method)
+            .field("url", &self.url)
+            .field("headers", &headers)
+            .field("body_len", &self.body.as_ref().map(Vec::len))
+            .finish()
+    }
+}
+
+/// Response returned by an `HttpClient`, with its body fully read.
+#[derive(Clone, Debug)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// Value of the first header named `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// HTTP backend sending the requests of `OpenTok`.
+///
+/// Implementations are provided for surf (`SurfClient`, with the
+/// `surf-client` feature, enabled by default) and reqwest (`ReqwestClient`,
+/// with the `reqwest-client` feature). Others can be plugged in with
+/// `OpenTokBuilder::with_http_client`.
+///
+/// Implementations should report requests that could not be completed with
+/// `OpenTokError::ConnectionError`, or `OpenTokError::Timeout` if they
+/// timed out. Responses, whatever their status, are returned as `Ok`.
+#[async_trait]
+pub trait HttpClient: Send + Sync {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, OpenTokError>;
+}
+
+/// Backend used when the default one is unavailable, because no backend
+/// feature is enabled or it could not be created, failing every request
+/// with the reason.
+struct UnavailableHttpClient(OpenTokError);
+
+#[async_trait]
+impl HttpClient for UnavailableHttpClient {
+    async fn send(&self, _: HttpRequest) -> Result<HttpResponse, OpenTokError> {
+        Err(self.0.clone())
+    }
+}
+
+#[cfg(all(
+    target_arch = "wasm32",
+    any(feature = "surf-client", feature = "reqwest-client")
+))]
+compile_error!(
+    "The surf-client and reqwest-client features are not supported on wasm32: \
+     disable the default features and provide an HttpClient with OpenTokBuilder::with_http_client"
+);
+
+/// The HTTP backend used when none is provided, with the given timeouts,
+/// proxy and connection pool: reqwest with the `runtime-tokio` feature,
+/// which takes precedence over the other runtime features, surf with
+/// `runtime-async-std` or `runtime-smol`. Otherwise surf is preferred over
+/// reqwest when both are enabled. If it cannot be created, for instance because
+/// the proxy is not supported by the backend, every request fails with the
+/// reason.
+#[allow(unused_variables)]
+pub fn default_http_client(
+    timeouts: &Timeouts,
+    proxy: Option<&Proxy>,
+    pool: &PoolConfig,
+) -> Arc<dyn HttpClient> {
+    #[cfg(all(feature = "surf-client", not(feature = "runtime-tokio")))]
+    let client = SurfClient::configured(timeouts.connect, proxy, pool);
+    #[cfg(all(
+        feature = "reqwest-client",
+        any(feature = "runtime-tokio", not(feature = "surf-client"))
+    ))]
+    let client = ReqwestClient::configured(timeouts.connect, proxy, pool);
+    #[cfg(not(any(feature = "surf-client", feature = "reqwest-client")))]
+    let client: Result<UnavailableHttpClient, _> = Err(OpenTokError::ConnectionError(
+        "No HTTP client: enable the surf-client or reqwest-client feature, \
+         or provide one with OpenTokBuilder::with_http_client"
+            .into(),
+    ));
+    match client {
+        Ok(client) => Arc::new(client),
+        Err(error) => Arc::new(UnavailableHttpClient(error)),
+    }
+}
+
+/// Error body returned by the OpenTok API along with non 2xx status codes.
+#[derive(Deserialize)]
+struct ErrorBody {
+    code: Option<i64>,
+    message: Option<String>,
+}
+
+/// Parses the value of a `Retry-After` header, either a number of seconds
+/// or an HTTP date, measured from `now`.
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    match value.trim().parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => httpdate::parse_http_date(value)
+            .ok()
+            .map(|date| date.duration_since(now).unwrap_or_default()),
+    }
+}
+
+/// Maps a non 2xx response to the most specific `OpenTokError` variant,
+/// using the `code` and `message` fields of the JSON body when present.
+fn api_error(
+    status: u16,
+    retry_after: Option<&str>,
+    body: String,
+    now: SystemTime,
+) -> OpenTokError {
+    let parsed = serde_json::from_str::<ErrorBody>(&body).ok();
+    let message = parsed
+        .as_ref()
+        .and_then(|error| error.message.clone())
+        .unwrap_or_else(|| body.clone());
+    match status {
+        403 => OpenTokError::Forbidden(message),
+        404 => OpenTokError::NotFound(message),
+        429 => OpenTokError::RateLimited {
+            retry_after: retry_after.and_then(|value| parse_retry_after(value, now)),
         },
-        Err(error) => Err(error.into()),
-    }
-}
-
-pub async fn post(
-    endpoint: &str,
-    api_key: &str,
-    api_secret: &str,
-    body: &impl Serialize,
-) -> Result<surf::Response, OpenTokError> {
-    let auth_header = auth_header(api_key, api_secret)?;
-    let mut req = surf::post(endpoint).build();
-    req.set_header(AUTH_HEADER, &auth_header);
-    req.set_header(ACCEPT, JSON);
-    req.body_form(body)
-        .map_err(|_| OpenTokError::EncodingError)?;
-    from_surf_response(surf::client().send(req).await).await
-}
-
-pub async fn get(
-    endpoint: &str,
-    api_key: &str,
-    api_secret: &str,
-) -> Result<surf::Response, OpenTokError> {
-    let auth_header = auth_header(api_key, api_secret)?;
-    let mut req = surf::get(endpoint).build();
-    req.set_header(AUTH_HEADER, &auth_header);
-    req.set_header(ACCEPT, JSON);
-    from_surf_response(surf::client().send(req).await).await
+        _ => match parsed {
+            Some(ErrorBody {
+                code,
+                message: Some(message),
+            }) => OpenTokError::ApiError {
+                code,
+                message,
+                status,
+            },
+            _ => match status {
+                400..=499 => OpenTokError::BadRequest(body),
+                500..=599 => OpenTokError::ServerError(body),
+                _ => OpenTokError::UnexpectedResponse(body),
+            },
+        },
+    }
+}
+
+/// Turns responses with a non 2xx status code into errors, measuring the
+/// delays given as HTTP dates from `now`.
+pub fn check_status(response: HttpResponse, now: SystemTime) -> Result<HttpResponse, OpenTokError> {
+    match response.status {
+        200..=299 => Ok(response),
+        status => {
+            let body = String::from_utf8_lossy(&response.body).into_owned();
+            Err(api_error(status, response.header(RETRY_AFTER), body, now))
+        }
+    }
+}
+
+/// Body of a successful response, as a string.
+pub(crate) fn body_string(response: HttpResponse) -> Result<String, OpenTokError> {
+    String::from_utf8(response.body)
+        .map_err(|error| OpenTokError::UnexpectedResponse(format!("{:?}", error)))
+}
+
+/// Client signing and sending all the requests of an `OpenTok` instance.
+#[derive(Clone)]
+pub struct Client {
+    failure_log: Option<Arc<FailureLog>>,
+    http: Arc<dyn HttpClient>,
+    jwt_cache: Arc<JwtCache>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    retry_policy: Option<RetryPolicy>,
+    time_source: Arc<dyn TimeSource>,
+    timeouts: Timeouts,
+    timer: Arc<dyn Timer>,
+}
+
+impl Client {
+    pub fn new(
+        http: Arc<dyn HttpClient>,
+        rate_limiter: Option<RateLimiter>,
+        retry_policy: Option<RetryPolicy>,
+        timeouts: Timeouts,
+        timer: Arc<dyn Timer>,
+    ) -> Self {
+        Self {
+            failure_log: None,
+            http,
+            jwt_cache: Arc::new(JwtCache::default()),
+            rate_limiter: rate_limiter.map(Arc::new),
+            retry_policy,
+            time_source: Arc::new(SystemTimeSource),
+            timeouts,
+            timer,
+        }
+    }
+
+    /// Timestamps and identifies the JWTs it signs with `time_source` and
+    /// `nonce_source`, and measures `Retry-After` dates with `time_source`.
+    pub fn with_sources(
+        mut self,
+        time_source: Arc<dyn TimeSource>,
+        nonce_source: Arc<dyn NonceSource>,
+    ) -> Self {
+        self.jwt_cache = Arc::new(JwtCache::new(time_source.clone(), nonce_source));
+        self.time_source = time_source;
+        self
+    }
+
+    /// Timer of the retry backoffs, timeouts and rate limiting.
+    pub fn timer(&self) -> &dyn Timer {
+        self.timer.as_ref()
+    }
+
+    /// Keeps the failed attempts of requests in `log`.
+    pub fn with_failure_log(mut self, log: FailureLog) -> Self {
+        self.failure_log = Some(Arc::new(log));
+        self
+    }
+
+    /// The failed attempts kept in the failure log, if any.
+    pub fn recent_failures(&self) -> Vec<FailedRequest> {
+        self.failure_log
+            .as_ref()
+            .map(|log| log.failures())
+            .unwrap_or_default()
+    }
+
+    /// Like `check_status`, measuring delays from the time of the time
+    /// source.
+    pub fn check_status(&self, response: HttpResponse) -> Result<HttpResponse, OpenTokError> {
+        check_status(response, self.time_source.now())
+    }
+
+    /// Returns a client sharing this client's connections, with a
+    /// different total timeout.
+    pub fn with_total_timeout(&self, total: Duration) -> Self {
+        let mut client = self.clone();
+        client.timeouts.total = Some(total);
+        client
+    }
+
+    /// Sends the request built by `build_request`, retrying according to
+    /// the retry policy, if any. Every attempt waits for the rate limiter,
+    /// if any. The request is built again for every
+    /// attempt, so that each one gets a JWT that has not expired.
+    /// A request rejected with a 401 status code is sent once more, with a
+    /// new JWT signed with the credentials then returned by the provider,
+    /// so that rotating the API secret does not fail requests signed with
+    /// the previous one. This attempt counts towards the retry policy's
+    /// `max_attempts`.
+    async fn send(
+        &self,
+        build_request: impl Fn() -> Result<HttpRequest, OpenTokError>,
+    ) -> Result<HttpResponse, OpenTokError> {
+        let mut attempt = 1;
+        let mut reauthenticated = false;
+        loop {
+            if let Some(rate_limiter) = &self.rate_limiter {
+                rate_limiter.acquire(self.timer.as_ref()).await;
+            }
+            let request = build_request()?;
+            let span = debug_span!(
+                "opentok_request",
+                method = ?request.method,
+                url = %request.url,
+                attempt,
+            );
+            let start = Instant::now();
+            let sent = self.failure_log.as_ref().map(|_| request.clone());
+            let response = self.http.send(request).instrument(span.clone()).await;
+            if let (Some(log), Some(request)) = (&self.failure_log, &sent) {
+                log.record(attempt, request, &response);
+            }
+            span.in_scope(|| match &response {
+                Ok(response) => debug!(
+                    status = response.status,
+                    latency_ms = start.elapsed().as_millis() as u64,
+                    "OpenTok API response"
+                ),
+                Err(error) => warn!(
+                    %error,
+                    latency_ms = start.elapsed().as_millis() as u64,
+                    "OpenTok API request failed"
+                ),
+            });
+            if matches!(&response, Ok(response) if response.status == 401) && !reauthenticated {
+                debug!("OpenTok API rejected the JWT, signing a new one");
+                self.jwt_cache.invalidate();
+                reauthenticated = true;
+                attempt += 1;
+                continue;
+            }
+            let policy = match &self.retry_policy {
+                Some(policy) if attempt < policy.max_attempts => policy,
+                _ => return response,
+            };
+            let delay = match &response {
+                Ok(response) if !RetryPolicy::is_retryable(response.status) => None,
+                Ok(response) => Some(
+                    response
+                        .header(RETRY_AFTER)
+                        .and_then(|value| parse_retry_after(value, self.time_source.now()))
+                        .unwrap_or_else(|| policy.backoff(attempt)),
+                ),
+                Err(_) => Some(policy.backoff(attempt)),
+            };
+            match delay {
+                Some(delay) => self.timer.sleep(delay).await,
+                None => return response,
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Signs a JWT for the following requests ahead of time, if the cached
+    /// one is missing or about to expire.
+    pub fn prepare_jwt(&self, api_key: &str, api_secret: &str) -> Result<(), OpenTokError> {
+        self.jwt_cache.get(api_key, api_secret).map(|_| ())
+    }
+
+    /// Builds a request signed with the cached JWT, or a new one if it is
+    /// about to expire or the credentials changed.
+    fn signed_request(
+        &self,
+        method: HttpMethod,
+        url: &str,
+        credentials: &dyn CredentialsProvider,
+    ) -> Result<HttpRequest, OpenTokError> {
+        let Credentials {
+            api_key,
+            api_secret,
+        } = credentials.credentials();
+        Ok(HttpRequest {
+            method,
+            url: url.into(),
+            headers: vec![
+                (
+                    AUTH_HEADER.into(),
+                    self.jwt_cache.get(&api_key, &api_secret)?,
+                ),
+                (ACCEPT.into(), JSON.into()),
+            ],
+            body: None,
+        })
+    }
+
+    /// Sends a POST request with `body` form encoded, returning the
+    /// response body.
+    pub async fn post(
+        &self,
+        endpoint: &Endpoint,
+        credentials: &dyn CredentialsProvider,
+        body: &impl Serialize,
+    ) -> Result<String, OpenTokError> {
+        let url = endpoint.url()?;
+        let body = serde_urlencoded::to_string(body).map_err(|_| OpenTokError::EncodingError)?;
+        timeout(self.timer.as_ref(), self.timeouts.total, async {
+            let response = self
+                .send(|| {
+                    let mut request = self.signed_request(HttpMethod::Post, url, credentials)?;
+                    request.headers.push((CONTENT_TYPE.into(), FORM.into()));
+                    request.body = Some(body.clone().into_bytes());
+                    Ok(request)
+                })
+                .await?;
+            body_string(self.check_status(response)?)
+        })
+        .await
+    }
+
+    /// Sends a POST request with `body` JSON encoded, returning the response
+    /// body.
+    #[cfg(feature = "insights")]
+    pub async fn post_json(
+        &self,
+        endpoint: &Endpoint,
+        credentials: &dyn CredentialsProvider,
+        body: &impl Serialize,
+    ) -> Result<String, OpenTokError> {
+        let body = serde_json::to_vec(body).map_err(|_| OpenTokError::EncodingError)?;
+        let response = self
+            .send_json(HttpMethod::Post, endpoint, credentials, Some(body))
+            .await?;
+        body_string(self.check_status(response)?)
+    }
+
+    /// Sends a request with `body`, already JSON encoded, if any, and hands
+    /// back the response whatever its status code is.
+    pub async fn send_json(
+        &self,
+        method: HttpMethod,
+        endpoint: &Endpoint,
+        credentials: &dyn CredentialsProvider,
+        body: Option<Vec<u8>>,
+    ) -> Result<HttpResponse, OpenTokError> {
+        let url = endpoint.url()?;
+        timeout(
+            self.timer.as_ref(),
+            self.timeouts.total,
+            self.send(|| {
+                let mut request = self.signed_request(method, url, credentials)?;
+                if let Some(body) = &body {
+                    request.headers.push((CONTENT_TYPE.into(), JSON.into()));
+                    request.body = Some(body.clone());
+                }
+                Ok(request)
+            }),
+        )
+        .await
+    }
+
+    /// Sends a GET request, returning the response body.
+    pub async fn get(
+        &self,
+        endpoint: &Endpoint,
+        credentials: &dyn CredentialsProvider,
+    ) -> Result<String, OpenTokError> {
+        let response = self.get_unchecked(endpoint, credentials).await?;
+        body_string(self.check_status(response)?)
+    }
+
+    /// Like `get`, but hands back the response whatever its status code is,
+    /// so callers can still look at the headers of a rejected request.
+    pub async fn get_unchecked(
+        &self,
+        endpoint: &Endpoint,
+        credentials: &dyn CredentialsProvider,
+    ) -> Result<HttpResponse, OpenTokError> {
+        let url = endpoint.url()?;
+        timeout(
+            self.timer.as_ref(),
+            self.timeouts.total,
+            self.send(|| self.signed_request(HttpMethod::Get, url, credentials)),
+        )
+        .await
+    }
+
+    /// Sends an unauthenticated request to `endpoint` for the sole purpose of
+    /// resolving its host and establishing a connection to it. The response
+    /// status is ignored.
+    pub async fn connect(&self, endpoint: &Endpoint) -> Result<(), OpenTokError> {
+        let request = HttpRequest {
+            method: HttpMethod::Head,
+            url: endpoint.url()?.into(),
+            headers: vec![],
+            body: None,
+        };
+        timeout(
+            self.timer.as_ref(),
+            self.timeouts.total,
+            self.http.send(request),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FuturesTimer, InMemoryCredentials};
+
+    use futures::executor::block_on;
+
+    /// `HttpClient` answering requests with canned responses, in order.
+    #[derive(Default)]
+    struct ScriptedClient {
+        responses: Mutex<Vec<HttpResponse>>,
+        requests: Mutex<Vec<HttpRequest>>,
+    }
+
+    impl ScriptedClient {
+        fn new(statuses: &[u16]) -> Arc<Self> {
+            let responses = statuses
+                .iter()
+                .rev()
+                .map(|status| HttpResponse {
+                    status: *status,
+                    headers: vec![(RETRY_AFTER.into(), "0".into())],
+                    body: b"{}".to_vec(),
+                })
+                .collect();
+            Arc::new(Self {
+                responses: Mutex::new(responses),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[async_trait]
+    impl HttpClient for ScriptedClient {
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse, OpenTokError> {
+            self.requests.lock().unwrap().push(request);
+            self.responses
+                .lock()
+                .unwrap()
+                .pop()
+                .ok_or_else(|| OpenTokError::ConnectionError("No more responses".into()))
+        }
+    }
+
+    fn credentials() -> Credentials {
+        Credentials::new("sancho".into(), "quijote".into())
+    }
+
+    #[test]
+    fn test_signed_requests() {
+        let http = ScriptedClient::new(&[200]);
+        let client = Client::new(
+            http.clone(),
+            None,
+            None,
+            Timeouts::default(),
+            Arc::new(FuturesTimer),
+        );
+        let endpoint = Endpoint::root().segment("session").segment("create");
+        let body = block_on(client.post(&endpoint, &credentials(), &[("a", "b c")]));
+        assert_eq!(body, Ok("{}".into()));
+        let requests = http.requests.lock().unwrap();
+        assert_eq!(requests[0].method, HttpMethod::Post);
+        assert_eq!(requests[0].url, "https://api.opentok.com/session/create");
+        assert_eq!(requests[0].body, Some(b"a=b+c".to_vec()));
+        assert!(requests[0]
+            .headers
+            .iter()
+            .any(|(name, _)| name == AUTH_HEADER));
+    }
+
+    #[test]
+    fn test_request_debug_redacts_jwt() {
+        let client = Client::new(
+            ScriptedClient::new(&[]),
+            None,
+            None,
+            Timeouts::default(),
+            Arc::new(FuturesTimer),
+        );
+        let request = client
+            .signed_request(HttpMethod::Get, "https://api.opentok.com", &credentials())
+            .unwrap();
+        let jwt = request.headers[0].1.clone();
+        let debug = format!("{:?}", request);
+        assert!(!debug.contains(&jwt));
+        assert!(debug.contains("<redacted>"));
+    }
+
+    #[test]
+    fn test_jwt_cache() {
+        let cache = JwtCache::default();
+        let jwt = cache.get("sancho", "quijote").unwrap();
+        assert_eq!(cache.get("sancho", "quijote"), Ok(jwt.clone()));
+        assert_ne!(cache.get("dulcinea", "quijote"), Ok(jwt.clone()));
+        assert_ne!(cache.get("sancho", "rocinante"), Ok(jwt.clone()));
+
+        let jwt = cache.get("sancho", "quijote").unwrap();
+        cache.cached.lock().unwrap().as_mut().unwrap().expires_at -=
+            JWT_LIFETIME_SECS - JWT_REFRESH_MARGIN_SECS;
+        assert_ne!(cache.get("sancho", "quijote"), Ok(jwt));
+    }
+
+    #[test]
+    fn test_retries() {
+        let endpoint = Endpoint::project("sancho");
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            ..Default::default()
+        };
+
+        let http = ScriptedClient::new(&[503, 429, 200]);
+        let client = Client::new(
+            http.clone(),
+            None,
+            Some(policy.clone()),
+            Timeouts::default(),
+            Arc::new(FuturesTimer),
+        );
+        assert!(block_on(client.get(&endpoint, &credentials())).is_ok());
+        assert_eq!(http.requests.lock().unwrap().len(), 3);
+
+        let http = ScriptedClient::new(&[503, 503, 503, 200]);
+        let client = Client::new(
+            http.clone(),
+            None,
+            Some(policy),
+            Timeouts::default(),
+            Arc::new(FuturesTimer),
+        );
+        assert!(matches!(
+            block_on(client.get(&endpoint, &credentials())),
+            Err(OpenTokError::ServerError(_))
+        ));
+        assert_eq!(http.requests.lock().unwrap().len(), 3);
+
+        let http = ScriptedClient::new(&[503, 200]);
+        let client = Client::new(
+            http.clone(),
+            None,
+            None,
+            Timeouts::default(),
+            Arc::new(FuturesTimer),
+        );
+        assert!(block_on(client.get(&endpoint, &credentials())).is_err());
+        assert_eq!(http.requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_reauthentication() {
+        let endpoint = Endpoint::project("sancho");
+        let provider = InMemoryCredentials::new(credentials());
+
+        let http = ScriptedClient::new(&[401, 200, 200]);
+        let client = Client::new(
+            http.clone(),
+            None,
+            None,
+            Timeouts::default(),
+            Arc::new(FuturesTimer),
+        );
+        block_on(client.get(&endpoint, &provider)).unwrap();
+        provider.set(Credentials::new("sancho".into(), "rocinante".into()));
+        block_on(client.get(&endpoint, &provider)).unwrap();
+        let requests = http.requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert_ne!(requests[0].headers[0].1, requests[1].headers[0].1);
+        assert_ne!(requests[1].headers[0].1, requests[2].headers[0].1);
+
+        let http = ScriptedClient::new(&[401, 401, 200]);
+        let client = Client::new(
+            http.clone(),
+            None,
+            None,
+            Timeouts::default(),
+            Arc::new(FuturesTimer),
+        );
+        assert!(block_on(client.get(&endpoint, &provider)).is_err());
+        assert_eq!(http.requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_api_error() {
+        let now = crate::time::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        assert_eq!(
+            api_error(
+                404,
+                None,
+                r#"{"code":404,"message":"Stream not found"}"#.into(),
+                now
+            ),
+            OpenTokError::NotFound("Stream not found".into())
+        );
+        assert_eq!(
+            api_error(403, None, "Forbidden".into(), now),
+            OpenTokError::Forbidden("Forbidden".into())
+        );
+        assert_eq!(
+            api_error(429, Some("2"), "".into(), now),
+            OpenTokError::RateLimited {
+                retry_after: Some(Duration::from_secs(2))
+            }
+        );
+        assert_eq!(
+            api_error(429, Some("Sun, 13 Sep 2020 12:26:50 GMT"), "".into(), now),
+            OpenTokError::RateLimited {
+                retry_after: Some(Duration::from_secs(10))
+            }
+        );
+        assert_eq!(
+            api_error(
+                409,
+                None,
+                r#"{"code":15004,"message":"Conflict"}"#.into(),
+                now
+            ),
+            OpenTokError::ApiError {
+                code: Some(15004),
+                message: "Conflict".into(),
+                status: 409,
+            }
+        );
+        assert!(matches!(
+            api_error(400, None, "not json".into(), now),
+            OpenTokError::BadRequest(_)
+        ));
+        assert!(matches!(
+            api_error(503, None, "<html></html>".into(), now),
+            OpenTokError::ServerError(_)
+        ));
+    }
 }
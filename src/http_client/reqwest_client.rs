@@ -0,0 +1,104 @@
+use super::{HttpClient, HttpMethod, HttpRequest, HttpResponse};
+use crate::{OpenTokError, PoolConfig, Proxy};
+
+use async_trait::async_trait;
+use reqwest::Method;
+use std::time::Duration;
+
+fn connection_error(error: reqwest::Error) -> OpenTokError {
+    if error.is_timeout() {
+        OpenTokError::Timeout
+    } else {
+        OpenTokError::ConnectionError(error.to_string())
+    }
+}
+
+/// `HttpClient` implementation backed by reqwest.
+/// Requests must be sent from within a tokio runtime.
+#[derive(Clone, Debug)]
+pub struct ReqwestClient {
+    client: reqwest::Client,
+}
+
+impl ReqwestClient {
+    /// Sends requests through an already configured reqwest client.
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    /// Sends requests through a new reqwest client, with the given timeout for
+    /// establishing connections. Fails with `OpenTokError::ConnectionError`
+    /// if the client cannot be created.
+    pub fn with_connect_timeout(connect: Option<Duration>) -> Result<Self, OpenTokError> {
+        Self::configured(connect, None, &PoolConfig::default())
+    }
+
+    pub(crate) fn configured(
+        connect: Option<Duration>,
+        proxy: Option<&Proxy>,
+        pool: &PoolConfig,
+    ) -> Result<Self, OpenTokError> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(connect) = connect {
+            builder = builder.connect_timeout(connect);
+        }
+        if let Some(idle_timeout) = pool.idle_timeout {
+            builder = builder.pool_idle_timeout(idle_timeout);
+        }
+        if let Some(max_idle_connections) = pool.max_idle_connections {
+            builder = builder.pool_max_idle_per_host(max_idle_connections);
+        }
+        if let Some(tcp_keepalive) = pool.tcp_keepalive {
+            builder = builder.tcp_keepalive(tcp_keepalive);
+        }
+        if let Some(proxy) = proxy {
+            let mut reqwest_proxy = reqwest::Proxy::all(proxy.uri.to_string()).map_err(|_| {
+                OpenTokError::Config(format!("Invalid proxy URL {}", proxy.redacted_uri()))
+            })?;
+            if let Some((username, password)) = &proxy.credentials {
+                reqwest_proxy = reqwest_proxy.basic_auth(username, password);
+            }
+            builder = builder.proxy(reqwest_proxy);
+        }
+        builder.build().map(Self::new).map_err(connection_error)
+    }
+}
+
+#[async_trait]
+impl HttpClient for ReqwestClient {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, OpenTokError> {
+        let method = match request.method {
+            HttpMethod::Get => Method::GET,
+            HttpMethod::Head => Method::HEAD,
+            HttpMethod::Post => Method::POST,
+            HttpMethod::Put => Method::PUT,
+            HttpMethod::Patch => Method::PATCH,
+            HttpMethod::Delete => Method::DELETE,
+        };
+        let mut req = self.client.request(method, &request.url);
+        for (name, value) in &request.headers {
+            req = req.header(name.as_str(), value.as_str());
+        }
+        if let Some(body) = request.body {
+            req = req.body(body);
+        }
+        let response = req.send().await.map_err(connection_error)?;
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
+        let body = response.bytes().await.map_err(connection_error)?.to_vec();
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
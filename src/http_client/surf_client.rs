@@ -0,0 +1,141 @@
+use super::{HttpClient, HttpMethod, HttpRequest, HttpResponse};
+use crate::{OpenTokError, PoolConfig, Proxy};
+
+use ::http_client::isahc::IsahcClient;
+use async_trait::async_trait;
+use isahc::auth::{Authentication, Credentials};
+use isahc::config::Configurable;
+use std::str::FromStr;
+use std::time::Duration;
+use surf::http::headers::{HeaderName, HeaderValue};
+use surf::http::Method;
+
+fn connection_error(error: surf::Error) -> OpenTokError {
+    match error.downcast_ref::<isahc::Error>() {
+        Some(isahc::Error::Timeout) => OpenTokError::Timeout,
+        _ => OpenTokError::ConnectionError(error.to_string()),
+    }
+}
+
+/// `HttpClient` implementation backed by surf.
+#[derive(Clone, Debug)]
+pub struct SurfClient {
+    client: surf::Client,
+}
+
+impl SurfClient {
+    /// Sends requests through an already configured surf client.
+    pub fn new(client: surf::Client) -> Self {
+        Self { client }
+    }
+
+    /// Sends requests through a new surf client, with the given timeout for
+    /// establishing connections. Fails with `OpenTokError::ConnectionError`
+    /// if the client cannot be created.
+    pub fn with_connect_timeout(connect: Option<Duration>) -> Result<Self, OpenTokError> {
+        Self::configured(connect, None, &PoolConfig::default())
+    }
+
+    pub(crate) fn configured(
+        connect: Option<Duration>,
+        proxy: Option<&Proxy>,
+        pool: &PoolConfig,
+    ) -> Result<Self, OpenTokError> {
+        let mut builder = isahc::HttpClient::builder();
+        if let Some(connect) = connect {
+            builder = builder.connect_timeout(connect);
+        }
+        if let Some(idle_timeout) = pool.idle_timeout {
+            builder = builder.connection_cache_ttl(idle_timeout);
+        }
+        if let Some(max_idle_connections) = pool.max_idle_connections {
+            builder = builder.connection_cache_size(max_idle_connections);
+        }
+        if let Some(tcp_keepalive) = pool.tcp_keepalive {
+            builder = builder.tcp_keepalive(tcp_keepalive);
+        }
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(proxy.uri.clone());
+            if let Some((username, password)) = &proxy.credentials {
+                builder = builder
+                    .proxy_authentication(Authentication::basic())
+                    .proxy_credentials(Credentials::new(username.as_str(), password.as_str()));
+            }
+        }
+        let isahc = builder
+            .build()
+            .map_err(|error| OpenTokError::ConnectionError(error.to_string()))?;
+        Ok(Self::new(surf::Client::with_http_client(
+            IsahcClient::from_client(isahc),
+        )))
+    }
+}
+
+#[async_trait]
+impl HttpClient for SurfClient {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, OpenTokError> {
+        let method = match request.method {
+            HttpMethod::Get => Method::Get,
+            HttpMethod::Head => Method::Head,
+            HttpMethod::Post => Method::Post,
+            HttpMethod::Put => Method::Put,
+            HttpMethod::Patch => Method::Patch,
+            HttpMethod::Delete => Method::Delete,
+        };
+        let url = surf::Url::parse(&request.url)
+            .map_err(|_| OpenTokError::BadRequest(request.url.clone()))?;
+        let mut req = surf::Request::new(method, url);
+        for (name, value) in &request.headers {
+            let invalid = |_| OpenTokError::BadRequest(format!("Invalid header {}", name));
+            req.append_header(
+                HeaderName::from_str(name).map_err(invalid)?,
+                HeaderValue::from_str(value).map_err(invalid)?,
+            );
+        }
+        if let Some(body) = request.body {
+            req.set_body(body);
+        }
+        let mut response = self.client.send(req).await.map_err(connection_error)?;
+        let headers = response
+            .iter()
+            .flat_map(|(name, values)| {
+                values
+                    .iter()
+                    .map(move |value| (name.to_string(), value.to_string()))
+            })
+            .collect();
+        let body = response.body_bytes().await.map_err(connection_error)?;
+        Ok(HttpResponse {
+            status: response.status().into(),
+            headers,
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_invalid_headers() {
+        let client = SurfClient::with_connect_timeout(None).unwrap();
+        for headers in [
+            vec![("X-Name".into(), "Dulcinea del Toboso ñ".into())],
+            vec![("X-Ñame".into(), "Dulcinea".into())],
+        ] {
+            let request = HttpRequest {
+                method: HttpMethod::Get,
+                url: "https://api.opentok.com/v2/project/1234".into(),
+                headers,
+                body: None,
+            };
+            assert!(matches!(
+                block_on(client.send(request)),
+                Err(OpenTokError::BadRequest(_))
+            ));
+        }
+    }
+}
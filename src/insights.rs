@@ -0,0 +1,470 @@
+//! Client of the OpenTok Insights API, which reports the usage and quality
+//! data of a project through a GraphQL endpoint authenticated with the
+//! same project JWT as the REST API.
+
+use crate::endpoint::Endpoint;
+use crate::time::{SystemTime, UNIX_EPOCH};
+use crate::{OpenTok, OpenTokError, SessionId, StreamId};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::instrument;
+
+/// Length of the intervals project usage is aggregated over.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UsageInterval {
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl fmt::Display for UsageInterval {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", format!("{:?}", self).to_uppercase())
+    }
+}
+
+/// Usage of a project over one interval.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProjectUsage {
+    /// Start of the interval, as an ISO 8601 date.
+    pub interval_start: String,
+    /// End of the interval, as an ISO 8601 date.
+    pub interval_end: String,
+    pub streamed_published_minutes: f64,
+    pub streamed_subscribed_minutes: f64,
+}
+
+/// Quality data of a session, for each of its meetings: the periods during
+/// which it had connected clients.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionQuality {
+    pub session_id: SessionId,
+    pub meetings: Vec<MeetingQuality>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeetingQuality {
+    pub meeting_id: String,
+    pub created_at: String,
+    /// When the meeting ended, unless it is still in progress.
+    pub destroyed_at: Option<String>,
+    /// Statistics of the streams published during the meeting.
+    pub streams: Vec<StreamQuality>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamQuality {
+    pub stream_id: StreamId,
+    pub stats: Vec<StreamStats>,
+}
+
+/// Statistics of a published stream, as sampled at `created_at`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamStats {
+    pub created_at: String,
+    pub audio_latency_ms: Option<f64>,
+    pub video_latency_ms: Option<f64>,
+    pub audio_bitrate_kbps: Option<f64>,
+    pub video_bitrate_kbps: Option<f64>,
+    pub audio_packet_loss: Option<f64>,
+    pub video_packet_loss: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct GraphQlRequest<'a> {
+    query: &'a str,
+    #[serde(skip_serializing_if = "serde_json::Value::is_null")]
+    variables: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct GraphQlResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+/// Page of results of an Insights query.
+#[derive(Deserialize)]
+struct Resources<T> {
+    resources: Vec<T>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectUsageData {
+    project: ProjectUsageProject,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectUsageProject {
+    project_data: Resources<ProjectUsageResource>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectUsageResource {
+    interval_start: String,
+    interval_end: String,
+    usage: Usage,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Usage {
+    streamed_published_minutes: f64,
+    streamed_subscribed_minutes: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionQualityData {
+    project: SessionQualityProject,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionQualityProject {
+    session_data: SessionData,
+}
+
+#[derive(Deserialize)]
+struct SessionData {
+    sessions: Resources<SessionResource>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionResource {
+    session_id: SessionId,
+    meetings: Resources<MeetingResource>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MeetingResource {
+    meeting_id: String,
+    created_at: String,
+    destroyed_at: Option<String>,
+    publishers: Resources<PublisherResource>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PublisherResource {
+    stream: PublishedStream,
+    stream_stats_collection: Resources<StreamStats>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PublishedStream {
+    stream_id: StreamId,
+}
+
+/// Query of the usage of a project. Arguments are inlined rather than
+/// passed as variables, which would tie the query to the names of the
+/// schema's scalar types.
+fn project_usage_query(
+    project_id: u64,
+    start: SystemTime,
+    end: Option<SystemTime>,
+    interval: UsageInterval,
+) -> String {
+    let end = end
+        .map(|end| format!(", end: {}", millis(end)))
+        .unwrap_or_default();
+    format!(
+        "{{
+  project(projectId: {}) {{
+    projectData(start: {}{}, interval: {}) {{
+      resources {{
+        intervalStart
+        intervalEnd
+        usage {{ streamedPublishedMinutes streamedSubscribedMinutes }}
+      }}
+    }}
+  }}
+}}",
+        project_id,
+        millis(start),
+        end,
+        interval
+    )
+}
+
+/// Query of the quality data of sessions, with the same inlined arguments.
+fn session_quality_query(
+    project_id: u64,
+    session_ids: &[SessionId],
+) -> Result<String, OpenTokError> {
+    let session_ids =
+        serde_json::to_string(session_ids).map_err(|_| OpenTokError::EncodingError)?;
+    Ok(format!(
+        "{{
+  project(projectId: {}) {{
+    sessionData {{
+      sessions(sessionIds: {}) {{
+        resources {{
+          sessionId
+          meetings {{
+            resources {{
+              meetingId
+              createdAt
+              destroyedAt
+              publishers {{
+                resources {{
+                  stream {{ streamId }}
+                  streamStatsCollection {{
+                    resources {{
+                      createdAt
+                      audioLatencyMs
+                      videoLatencyMs
+                      audioBitrateKbps
+                      videoBitrateKbps
+                      audioPacketLoss
+                      videoPacketLoss
+                    }}
+                  }}
+                }}
+              }}
+            }}
+          }}
+        }}
+      }}
+    }}
+  }}
+}}",
+        project_id, session_ids
+    ))
+}
+
+fn millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl OpenTok {
+    /// Runs `query` against the Insights API with `variables`, returning its
+    /// `data`. Fails with `OpenTokError::GraphQl`, with their messages, if
+    /// the query reports errors.
+    #[instrument(level = "debug", skip(self, variables), err)]
+    pub async fn insights_query<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<T, OpenTokError> {
+        self.bounded(async move {
+            let response_str = self
+                .client
+                .post_json(
+                    &Endpoint::insights(),
+                    self.credentials.as_ref(),
+                    &GraphQlRequest { query, variables },
+                )
+                .await?;
+            let response = serde_json::from_str::<GraphQlResponse<T>>(&response_str)
+                .map_err(|_| OpenTokError::UnexpectedResponse(response_str.clone()))?;
+            if !response.errors.is_empty() {
+                let messages: Vec<_> = response
+                    .errors
+                    .into_iter()
+                    .map(|error| error.message)
+                    .collect();
+                return Err(OpenTokError::GraphQl(messages));
+            }
+            response
+                .data
+                .ok_or(OpenTokError::UnexpectedResponse(response_str))
+        })
+        .await
+    }
+
+    /// Usage of the project from `start` to `end`, or until now, aggregated
+    /// over intervals of `interval`.
+    pub async fn project_usage(
+        &self,
+        start: SystemTime,
+        end: Option<SystemTime>,
+        interval: UsageInterval,
+    ) -> Result<Vec<ProjectUsage>, OpenTokError> {
+        let query = project_usage_query(self.project_id()?, start, end, interval);
+        let data: ProjectUsageData = self.insights_query(&query, serde_json::Value::Null).await?;
+        Ok(data
+            .project
+            .project_data
+            .resources
+            .into_iter()
+            .map(|resource| ProjectUsage {
+                interval_start: resource.interval_start,
+                interval_end: resource.interval_end,
+                streamed_published_minutes: resource.usage.streamed_published_minutes,
+                streamed_subscribed_minutes: resource.usage.streamed_subscribed_minutes,
+            })
+            .collect())
+    }
+
+    /// Quality data of the sessions with the given IDs. Sessions unknown to
+    /// Insights are left out.
+    pub async fn session_quality(
+        &self,
+        session_ids: &[SessionId],
+    ) -> Result<Vec<SessionQuality>, OpenTokError> {
+        let query = session_quality_query(self.project_id()?, session_ids)?;
+        let data: SessionQualityData = self.insights_query(&query, serde_json::Value::Null).await?;
+        Ok(data
+            .project
+            .session_data
+            .sessions
+            .resources
+            .into_iter()
+            .map(|session| SessionQuality {
+                session_id: session.session_id,
+                meetings: session
+                    .meetings
+                    .resources
+                    .into_iter()
+                    .map(|meeting| MeetingQuality {
+                        meeting_id: meeting.meeting_id,
+                        created_at: meeting.created_at,
+                        destroyed_at: meeting.destroyed_at,
+                        streams: meeting
+                            .publishers
+                            .resources
+                            .into_iter()
+                            .map(|publisher| StreamQuality {
+                                stream_id: publisher.stream.stream_id,
+                                stats: publisher.stream_stats_collection.resources,
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+
+    /// The API key, which Insights takes as a numeric project ID.
+    fn project_id(&self) -> Result<u64, OpenTokError> {
+        let api_key = self.credentials.credentials().api_key;
+        api_key
+            .parse()
+            .map_err(|_| OpenTokError::Config(format!("API key {} is not a project ID", api_key)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::CannedClient;
+
+    use futures::executor::block_on;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Instance of project 12345 answered with `response` by the Insights
+    /// API, and the server keeping the queries it was sent.
+    fn opentok(response: &'static str) -> (OpenTok, Arc<CannedClient>) {
+        let server = CannedClient::new(200, response);
+        let opentok = OpenTok::builder("12345".into(), "quijote".into())
+            .with_http_client(server.clone())
+            .build();
+        (opentok, server)
+    }
+
+    /// Checks that every query `server` was sent is about project 12345.
+    fn assert_project_queries(server: &CannedClient) {
+        for request in server.requests() {
+            assert_eq!(request.url, "https://insights.opentok.com/graphql");
+            let body: serde_json::Value = serde_json::from_slice(&request.body.unwrap()).unwrap();
+            assert!(body["query"]
+                .as_str()
+                .unwrap()
+                .contains("project(projectId: 12345)"));
+        }
+    }
+
+    #[test]
+    fn test_project_usage() {
+        let (opentok, server) = opentok(
+            r#"{"data":{"project":{"projectData":{"resources":[{
+                "intervalStart":"2021-10-01T00:00:00.000Z",
+                "intervalEnd":"2021-10-02T00:00:00.000Z",
+                "usage":{"streamedPublishedMinutes":12.5,"streamedSubscribedMinutes":30}
+            }]}}}}"#,
+        );
+        let start = UNIX_EPOCH + Duration::from_secs(1_633_046_400);
+        assert_eq!(
+            block_on(opentok.project_usage(start, None, UsageInterval::Daily)),
+            Ok(vec![ProjectUsage {
+                interval_start: "2021-10-01T00:00:00.000Z".into(),
+                interval_end: "2021-10-02T00:00:00.000Z".into(),
+                streamed_published_minutes: 12.5,
+                streamed_subscribed_minutes: 30.0,
+            }])
+        );
+        assert_project_queries(&server);
+    }
+
+    #[test]
+    fn test_session_quality() {
+        let (opentok, server) = opentok(
+            r#"{"data":{"project":{"sessionData":{"sessions":{"resources":[{
+                "sessionId":"1_MX4",
+                "meetings":{"resources":[{
+                    "meetingId":"meeting",
+                    "createdAt":"2021-10-01T10:00:00.000Z",
+                    "destroyedAt":null,
+                    "publishers":{"resources":[{
+                        "stream":{"streamId":"stream"},
+                        "streamStatsCollection":{"resources":[{
+                            "createdAt":"2021-10-01T10:00:30.000Z",
+                            "audioLatencyMs":40,
+                            "videoLatencyMs":null,
+                            "audioBitrateKbps":32,
+                            "videoBitrateKbps":null,
+                            "audioPacketLoss":0.01,
+                            "videoPacketLoss":null
+                        }]}
+                    }]}
+                }]}
+            }]}}}}}"#,
+        );
+        let sessions = block_on(opentok.session_quality(&["1_MX4".into()])).unwrap();
+        assert_eq!(sessions[0].session_id, "1_MX4".into());
+        let meeting = &sessions[0].meetings[0];
+        assert_eq!(meeting.destroyed_at, None);
+        assert_eq!(meeting.streams[0].stream_id, "stream".into());
+        assert_eq!(meeting.streams[0].stats[0].audio_latency_ms, Some(40.0));
+        assert_project_queries(&server);
+    }
+
+    #[test]
+    fn test_insights_errors() {
+        let (opentok, server) =
+            opentok(r#"{"data":null,"errors":[{"message":"Unknown project"}]}"#);
+        assert_eq!(
+            block_on(opentok.session_quality(&[])),
+            Err(OpenTokError::GraphQl(vec!["Unknown project".into()]))
+        );
+        assert_project_queries(&server);
+
+        let opentok = OpenTok::builder("sancho".into(), "quijote".into())
+            .with_http_client(CannedClient::new(200, "{}"))
+            .build();
+        assert!(matches!(
+            block_on(opentok.session_quality(&[])),
+            Err(OpenTokError::Config(_))
+        ));
+    }
+}
@@ -1,145 +1,114 @@
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::expect_used,
+        clippy::indexing_slicing,
+        clippy::panic,
+        clippy::string_slice,
+        clippy::unwrap_used
+    )
+)]
+
 extern crate rustc_serialize;
 
-use rand::Rng;
+use futures::Future;
+use opentok_types::time;
 use rustc_serialize::hex::ToHex;
-use serde::{Deserialize, Serialize};
 use std::fmt;
-use std::time::{SystemTime, UNIX_EPOCH};
-use thiserror::Error;
-
-mod http_client;
-
-static SERVER_URL: &str = "https://api.opentok.com";
-static API_ENDPOINT_PATH_START: &str = "/v2/project/";
-
-/// Unique session identifier.
-pub type SessionId = String;
-
-/// OpenTokError enumerates all possible errors returned by this library.
-#[derive(Debug, Error, PartialEq)]
-pub enum OpenTokError {
-    #[error("Bad request {0}")]
-    BadRequest(String),
-    #[error("Cannot encode request")]
-    EncodingError,
-    #[error("OpenTok server error {0}")]
-    ServerError(String),
-    #[error("Unexpected response {0}")]
-    UnexpectedResponse(String),
-    #[error("Unknown error")]
-    __Unknown,
-}
-
-impl From<surf::Error> for OpenTokError {
-    fn from(error: surf::Error) -> OpenTokError {
-        match error.status().into() {
-            400..=499 => OpenTokError::BadRequest(error.to_string()),
-            500..=599 => OpenTokError::ServerError(error.to_string()),
-            _ => OpenTokError::__Unknown,
-        }
-    }
-}
-
-/// Determines whether a session will transmit streams using the OpenTok Media Router
-/// or not.
-#[derive(Debug, PartialEq)]
-pub enum MediaMode {
-    /// The session will try to transmit streams directly between clients.
-    Relayed,
-    /// The session will transmit streams using the OpenTok Media Router.
-    Routed,
-}
-
-impl fmt::Display for MediaMode {
-    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        write!(formatter, "{}", format!("{:?}", self).to_lowercase())
-    }
-}
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::instrument;
 
-/// Determines whether a session is automatically archived or not.
-/// Archiving is currently unsupported.
-#[derive(Debug)]
-pub enum ArchiveMode {
-    /// The session will always be archived automatically.
-    Always,
-    /// A POST request to /archive is required to archive the session.
-    /// Currently unsupported.
-    Manual,
-}
-
-impl fmt::Display for ArchiveMode {
-    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        write!(formatter, "{}", format!("{:?}", self).to_lowercase())
-    }
-}
-
-/// OpenTok Session options to be provided at Session creation time.
-#[derive(Default)]
-pub struct SessionOptions<'a> {
-    /// An IP address that the OpenTok servers will use to situate the session in the global
-    /// OpenTok network. If you do not set a location hint, the OpenTok servers will be based
-    /// on the first client connecting to the session.
-    pub location: Option<&'a str>,
-    /// Determines whether the session will transmit streams using the OpenTok Media Router
-    /// ("routed") or not ("relayed"). By default, the setting is "relayed".
-    /// With the media_mode parameter set to "relayed", the session will attempt to transmit
-    /// streams directly between clients. If clients cannot connect due to firewall restrictions,
-    /// the session uses the OpenTok TURN server to relay audio-video streams.
-    pub media_mode: Option<MediaMode>,
-    /// Whether the session is automatically archived ("always") or not ("manual").
-    /// By default, the setting is "manual". To archive the session (either automatically or not),
-    /// you must set the media_mode parameter to "routed".
-    /// Archiving is currently unsupported.
-    pub archive_mode: Option<ArchiveMode>,
-}
-
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct CreateSessionBody<'a> {
-    archive_mode: String,
-    location: Option<&'a str>,
-    #[serde(rename = "p2p.preference")]
-    p2p_preference: &'a str,
-}
-
-impl<'a> From<SessionOptions<'a>> for CreateSessionBody<'a> {
-    fn from(options: SessionOptions) -> CreateSessionBody {
-        CreateSessionBody {
-            archive_mode: options
-                .archive_mode
-                .map(|mode| mode.to_string())
-                .unwrap_or_else(|| "manual".into()),
-            location: options.location,
-            p2p_preference: options
-                .media_mode
-                .map(|mode| {
-                    if mode == MediaMode::Relayed {
-                        "enabled"
-                    } else {
-                        "disabled"
-                    }
-                })
-                .unwrap_or("disabled"),
-        }
-    }
-}
+use failures::FailureLog;
+use middleware::MiddlewareClient;
+use rate_limit::RateLimiter;
+use time::Instant;
+use timeout::timeout;
 
-#[derive(Deserialize)]
-struct CreateSessionResponse {
-    session_id: String,
-}
+mod aggregate_error;
+mod api;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod credentials;
+mod endpoint;
+mod etag_cache;
+mod failures;
+mod health;
+mod http_client;
+#[cfg(feature = "insights")]
+mod insights;
+mod middleware;
+#[cfg(feature = "test-util")]
+mod mock;
+mod pagination;
+mod pool;
+mod proxy;
+mod rate_limit;
+mod region_probe;
+mod retry;
+mod session_lock;
+mod session_store;
+mod signed_client;
+mod sources;
+#[cfg(test)]
+mod test_util;
+mod timeout;
+mod timer;
+mod token_minter;
+mod usage;
+mod version;
 
-#[derive(Debug)]
-pub enum TokenRole {
-    Publisher,
-    Subscriber,
-    Moderator,
-}
+pub use aggregate_error::AggregateError;
+pub use api::OpenTokApi;
+pub use credentials::{Credentials, CredentialsProvider, InMemoryCredentials};
+pub use endpoint::Endpoint;
+pub use etag_cache::EtagCache;
+pub use failures::{FailedRequest, FailureSampling};
+pub use health::{ClockSkew, Health, HealthStatus};
+#[cfg(feature = "reqwest-client")]
+pub use http_client::ReqwestClient;
+#[cfg(feature = "surf-client")]
+pub use http_client::SurfClient;
+pub use http_client::{HttpClient, HttpMethod, HttpRequest, HttpResponse};
+#[cfg(feature = "insights")]
+pub use insights::{
+    MeetingQuality, ProjectUsage, SessionQuality, StreamQuality, StreamStats, UsageInterval,
+};
+pub use middleware::{Middleware, Next};
+#[cfg(feature = "test-util")]
+pub use mock::{MockCall, MockOpenTok};
+pub use opentok_types::{
+    ArchiveId, ArchiveMode, BroadcastId, ConnectionId, CreateSessionBody, CreateSessionResponse,
+    MediaMode, OpenTokError, ParsedSessionId, SessionId, SessionOptions, SessionOptionsBuilder,
+    StreamId, StreamInfo, TokenRole, VideoType, WebhookVerifier,
+};
+pub use pagination::{paginate, Page};
+pub use pool::PoolConfig;
+pub use proxy::Proxy;
+pub use rate_limit::RateLimit;
+pub use region_probe::{RegionLatency, RegionProber};
+pub use retry::RetryPolicy;
+pub use session_lock::{InMemorySessionLock, SessionLock, SessionLockGuard};
+pub use session_store::{InMemorySessionStore, RoomSession, SessionStore, StoredSession};
+pub use signed_client::{decode_json, SignedClient};
+pub use sources::{NonceSource, RandomNonceSource, SystemTimeSource, TimeSource};
+pub use timeout::Timeouts;
+#[cfg(feature = "runtime-async-std")]
+pub use timer::AsyncStdTimer;
+#[cfg(feature = "runtime-smol")]
+pub use timer::SmolTimer;
+#[cfg(feature = "runtime-tokio")]
+pub use timer::TokioTimer;
+pub use timer::{FuturesTimer, Timer};
+pub use token_minter::{LocalTokenMinter, RemoteTokenMinter, TokenMinter};
+pub use usage::{UsageCounters, UsageEvent, UsageSink};
+pub use version::{api_version, API_VERSION, SDK_VERSION};
 
-impl fmt::Display for TokenRole {
-    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        write!(formatter, "{}", format!("{:?}", self).to_lowercase())
-    }
+/// Internals exposed to the benchmarks. Not part of the public API.
+#[cfg(feature = "benchmarks")]
+#[doc(hidden)]
+pub mod benchmarks {
+    pub use crate::http_client::auth_header;
 }
 
 #[derive(Debug)]
@@ -152,17 +121,12 @@ struct TokenData<'a> {
 }
 
 impl<'a> TokenData<'a> {
-    pub fn new(session_id: &'a str, role: TokenRole) -> Self {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards, Doc!")
-            .as_secs();
-        let mut rng = rand::thread_rng();
+    pub fn new(session_id: &'a str, role: TokenRole, now: u64, skew: u64, nonce: u64) -> Self {
         Self {
             session_id,
-            create_time: now,
+            create_time: now.saturating_sub(skew),
             expire_time: now + (60 * 60 * 24),
-            nonce: rng.gen::<u64>(),
+            nonce,
             role,
         }
     }
@@ -178,36 +142,255 @@ impl<'a> fmt::Display for TokenData<'a> {
     }
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum VideoType {
-    Camera,
-    Screen,
-    Custom,
-}
-
-impl fmt::Display for VideoType {
-    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        write!(formatter, "{}", format!("{:?}", self).to_lowercase())
-    }
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-#[allow(dead_code)]
-pub struct StreamInfo {
-    id: String,
-    video_type: VideoType,
-    name: String,
-    layout_class_list: Vec<String>,
+/// Signs a token for `session_id` with the credentials of a project,
+/// created at `now` seconds since the Unix epoch, minus `skew` seconds.
+fn sign_token(
+    api_key: &str,
+    api_secret: &str,
+    session_id: &SessionId,
+    role: TokenRole,
+    now: u64,
+    skew: u64,
+    nonce: u64,
+) -> String {
+    let token_data = TokenData::new(session_id.as_str(), role, now, skew, nonce);
+    let signed =
+        hmacsha1::hmac_sha1(api_secret.as_bytes(), token_data.to_string().as_bytes()).to_hex();
+    let decoded = format!("partner_id={}&sig={}:{}", api_key, signed, token_data);
+    let encoded = base64::encode(decoded);
+    format!("T1=={}", encoded)
 }
 
 /// Top level entry point exposing the OpenTok server SDK functionality.
 /// Contains methods for creating OpenTok sessions, generating tokens and
 /// getting information about streams.
+#[derive(Clone)]
 pub struct OpenTok {
-    api_key: String,
-    api_secret: String,
+    credentials: Arc<dyn CredentialsProvider>,
+    client: http_client::Client,
+    hard_deadline: Option<Duration>,
+    /// Signs the tokens not minted by `token_minter`.
+    local_minter: LocalTokenMinter,
+    session_fallback: Option<SessionFallback>,
+    session_lock: Option<Arc<dyn SessionLock>>,
+    time_source: Arc<dyn TimeSource>,
+    token_minter: Option<Arc<dyn TokenMinter>>,
+    usage_sink: Option<Arc<dyn UsageSink>>,
+}
+
+/// Configuration of the fallback of `OpenTok::create_session_for_room`.
+#[derive(Clone)]
+struct SessionFallback {
+    store: Arc<dyn SessionStore>,
+    max_age: Duration,
+}
+
+/// Builder for `OpenTok` instances that need more than the default
+/// configuration.
+pub struct OpenTokBuilder {
+    credentials: Arc<dyn CredentialsProvider>,
+    failure_sampling: Option<FailureSampling>,
+    hard_deadline: Option<Duration>,
+    http_client: Option<Arc<dyn HttpClient>>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    nonce_source: Option<Arc<dyn NonceSource>>,
+    pool: PoolConfig,
+    proxy: Option<Proxy>,
+    rate_limit: Option<RateLimit>,
+    retry_policy: Option<RetryPolicy>,
+    session_fallback: Option<SessionFallback>,
+    session_lock: Option<Arc<dyn SessionLock>>,
+    time_source: Option<Arc<dyn TimeSource>>,
+    timeouts: Timeouts,
+    timer: Option<Arc<dyn Timer>>,
+    token_clock_skew: Duration,
+    token_minter: Option<Arc<dyn TokenMinter>>,
+    usage_sink: Option<Arc<dyn UsageSink>>,
+}
+
+impl OpenTokBuilder {
+    /// Keeps a sample of the failed requests, made available by
+    /// `OpenTok::recent_failures`. By default, none are kept.
+    pub fn with_failure_sampling(mut self, sampling: FailureSampling) -> Self {
+        self.failure_sampling = Some(sampling);
+        self
+    }
+
+    /// Makes every operation of `OpenTok` fail with `OpenTokError::Timeout`
+    /// once it has run for `deadline`, whatever it is waiting for: unlike
+    /// `Timeouts::total`, which bounds each request to the OpenTok API, it
+    /// also bounds session stores, token minters and HTTP backends that
+    /// would ignore their own timeouts. By default, operations have no
+    /// deadline.
+    pub fn with_hard_deadline(mut self, deadline: Duration) -> Self {
+        self.hard_deadline = Some(deadline);
+        self
+    }
+
+    /// Sends requests through `client` instead of the default HTTP backend.
+    /// The connect timeout set with `with_timeouts`, the proxy set with
+    /// `with_proxy` and the connection pool set with `with_pool_config` are
+    /// then up to the configuration of `client`.
+    pub fn with_http_client(mut self, client: impl HttpClient + 'static) -> Self {
+        self.http_client = Some(Arc::new(client));
+        self
+    }
+
+    /// Runs every request through `middleware`, after the middlewares
+    /// added before it.
+    pub fn with_middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Draws the nonces of tokens and the IDs of JWTs from `source`, instead
+    /// of the thread-local random generator. Meant for tests needing
+    /// deterministic tokens.
+    pub fn with_nonce_source(mut self, source: impl NonceSource + 'static) -> Self {
+        self.nonce_source = Some(Arc::new(source));
+        self
+    }
+
+    /// Configures the connections kept open by the default HTTP backends,
+    /// instead of `PoolConfig::default()`.
+    pub fn with_pool_config(mut self, pool: PoolConfig) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    /// Sends requests through `proxy`.
+    /// Without it, the default HTTP backends use the proxy set in the
+    /// `HTTPS_PROXY` environment variable, if any.
+    pub fn with_proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Delays requests exceeding `limit` until they fit in it, rather than
+    /// sending them and getting them rejected. Each attempt of a retried
+    /// request counts. By default, requests are not limited.
+    pub fn with_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limit = Some(limit);
+        self
+    }
+
+    /// Retries requests failing with transient errors according to `policy`.
+    /// By default, requests are not retried.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Remembers the sessions created with `OpenTok::create_session_for_room`
+    /// in `store`, and falls back to them when creating a new session fails
+    /// with a transient error, as long as they are not older than `max_age`.
+    pub fn with_session_fallback(
+        mut self,
+        store: Arc<dyn SessionStore>,
+        max_age: Duration,
+    ) -> Self {
+        self.session_fallback = Some(SessionFallback { store, max_age });
+        self
+    }
+
+    /// Holds the lock of the room in `lock` while creating and storing its
+    /// session in `OpenTok::create_session_for_room`. Along with a session
+    /// store, concurrent calls for the same room, from this instance or
+    /// others sharing the lock and the store, then return the same new
+    /// session instead of creating one each.
+    pub fn with_session_lock(mut self, lock: Arc<dyn SessionLock>) -> Self {
+        self.session_lock = Some(lock);
+        self
+    }
+
+    /// Timestamps tokens and JWTs with the time read from `source`, instead
+    /// of the system clock. Meant for tests needing deterministic tokens.
+    pub fn with_time_source(mut self, source: impl TimeSource + 'static) -> Self {
+        self.time_source = Some(Arc::new(source));
+        self
+    }
+
+    /// Bounds how long requests can take. By default, requests have no timeout.
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Sleeps with `timer` for retry backoffs, timeouts and rate limiting,
+    /// instead of the timer of the runtime selected with the `runtime-*`
+    /// features.
+    pub fn with_timer(mut self, timer: impl Timer + 'static) -> Self {
+        self.timer = Some(Arc::new(timer));
+        self
+    }
+
+    /// Backdates the creation time of the tokens signed by this instance by
+    /// `skew`, so that clients whose clocks are slightly ahead don't reject
+    /// them as not yet valid. Their expiry is left unchanged.
+    /// By default, tokens are created at the current time.
+    pub fn with_token_clock_skew(mut self, skew: Duration) -> Self {
+        self.token_clock_skew = skew;
+        self
+    }
+
+    /// Mints the tokens of `OpenTok::mint_token` with `minter`, for instance
+    /// a `RemoteTokenMinter` asking a central signing service for them.
+    /// By default, they are signed locally like those of
+    /// `OpenTok::generate_token`.
+    pub fn with_token_minter(mut self, minter: Arc<dyn TokenMinter>) -> Self {
+        self.token_minter = Some(minter);
+        self
+    }
+
+    /// Reports the sessions created and tokens generated with this instance
+    /// to `sink`.
+    pub fn with_usage_sink(mut self, sink: Arc<dyn UsageSink>) -> Self {
+        self.usage_sink = Some(sink);
+        self
+    }
+
+    pub fn build(self) -> OpenTok {
+        let mut http = self.http_client.unwrap_or_else(|| {
+            http_client::default_http_client(&self.timeouts, self.proxy.as_ref(), &self.pool)
+        });
+        if !self.middlewares.is_empty() {
+            http = Arc::new(MiddlewareClient::new(http, self.middlewares));
+        }
+        let mut client = http_client::Client::new(
+            http,
+            self.rate_limit.map(RateLimiter::new),
+            self.retry_policy,
+            self.timeouts,
+            self.timer.unwrap_or_else(timer::default_timer),
+        );
+        let time_source = self
+            .time_source
+            .unwrap_or_else(|| Arc::new(SystemTimeSource));
+        let nonce_source = self
+            .nonce_source
+            .unwrap_or_else(|| Arc::new(RandomNonceSource));
+        client = client.with_sources(time_source.clone(), nonce_source.clone());
+        if let Some(sampling) = self.failure_sampling {
+            client = client.with_failure_log(FailureLog::new(sampling, time_source.clone()));
+        }
+        let local_minter = LocalTokenMinter::with_sources(
+            self.credentials.clone(),
+            time_source.clone(),
+            nonce_source,
+            self.token_clock_skew,
+        );
+        OpenTok {
+            credentials: self.credentials,
+            client,
+            hard_deadline: self.hard_deadline,
+            local_minter,
+            session_fallback: self.session_fallback,
+            session_lock: self.session_lock,
+            time_source,
+            token_minter: self.token_minter,
+            usage_sink: self.usage_sink,
+        }
+    }
 }
 
 impl OpenTok {
@@ -215,70 +398,331 @@ impl OpenTok {
     /// the API secret for your TokBox account. Do not publicly share your
     /// API secret.
     pub fn new(api_key: String, api_secret: String) -> Self {
-        Self {
-            api_key,
-            api_secret,
+        Self::builder(api_key, api_secret).build()
+    }
+
+    /// Create a builder for an instance of OpenTok, with the same
+    /// requirements as `OpenTok::new`.
+    pub fn builder(api_key: String, api_secret: String) -> OpenTokBuilder {
+        Self::builder_with_credentials(Arc::new(Credentials::new(api_key, api_secret)))
+    }
+
+    /// Create an instance of OpenTok with the credentials found in the
+    /// `OPENTOK_API_KEY` and `OPENTOK_API_SECRET` environment variables.
+    /// Fails with `OpenTokError::Config` if either is not set.
+    pub fn from_env() -> Result<Self, OpenTokError> {
+        Credentials::from_env()
+            .map(|credentials| Self::builder_with_credentials(Arc::new(credentials)).build())
+    }
+
+    /// Create a builder for an instance of OpenTok taking its credentials
+    /// from `provider` for every request and token, so that they can be
+    /// rotated while it runs.
+    pub fn builder_with_credentials(provider: Arc<dyn CredentialsProvider>) -> OpenTokBuilder {
+        OpenTokBuilder {
+            credentials: provider,
+            failure_sampling: None,
+            hard_deadline: None,
+            http_client: None,
+            middlewares: vec![],
+            nonce_source: None,
+            pool: PoolConfig::default(),
+            proxy: None,
+            rate_limit: None,
+            retry_policy: None,
+            session_fallback: None,
+            session_lock: None,
+            time_source: None,
+            timeouts: Timeouts::default(),
+            timer: None,
+            token_clock_skew: Duration::from_secs(0),
+            token_minter: None,
+            usage_sink: None,
         }
     }
 
+    /// Returns a copy of this instance, sharing its connections, whose
+    /// requests fail with `OpenTokError::Timeout` if they take longer than
+    /// `total`. Meant to give a single call a tighter or looser bound than
+    /// the one configured with `OpenTokBuilder::with_timeouts`.
+    pub fn with_timeout(&self, total: Duration) -> OpenTok {
+        OpenTok {
+            client: self.client.with_total_timeout(total),
+            ..self.clone()
+        }
+    }
+
+    /// Resolves the OpenTok API host and establishes a connection to it,
+    /// and signs the JWT authenticating the following requests, so that
+    /// the first request after a cold start does not pay the DNS, TLS and
+    /// signature setup cost.
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn warm_up(&self) -> Result<(), OpenTokError> {
+        self.bounded(async move {
+            let credentials = self.credentials.credentials();
+            self.client
+                .prepare_jwt(&credentials.api_key, &credentials.api_secret)?;
+            self.client.connect(&Endpoint::root()).await
+        })
+        .await
+    }
+
     /// Creates a new OpenTok session.
     /// On success, a session ID is provided.
+    #[instrument(level = "debug", skip(self, options), err)]
     pub async fn create_session<'a>(
         &self,
         options: SessionOptions<'a>,
-    ) -> Result<String, OpenTokError> {
-        let body: CreateSessionBody = options.into();
-        let endpoint = format!("{}{}", SERVER_URL, "/session/create");
-        let mut response =
-            http_client::post(&endpoint, &self.api_key, &self.api_secret, &body).await?;
-        let response_str = response.body_string().await?;
-        let mut response: Vec<CreateSessionResponse> =
-            serde_json::from_str::<Vec<CreateSessionResponse>>(&response_str)
-                .map_err(|_| OpenTokError::UnexpectedResponse(response_str.clone()))?;
-        assert_eq!(response.len(), 1);
-        match response.pop() {
-            Some(session) => Ok(session.session_id),
-            None => Err(OpenTokError::UnexpectedResponse(response_str)),
+    ) -> Result<SessionId, OpenTokError> {
+        self.bounded(async move {
+            options.validate()?;
+            let body: CreateSessionBody = options.into();
+            let endpoint = Endpoint::root().segment("session").segment("create");
+            let response_str = self
+                .client
+                .post(&endpoint, self.credentials.as_ref(), &body)
+                .await?;
+            let mut response: Vec<CreateSessionResponse> =
+                serde_json::from_str::<Vec<CreateSessionResponse>>(&response_str)
+                    .map_err(|_| OpenTokError::UnexpectedResponse(response_str.clone()))?;
+            match response.pop() {
+                Some(session) if response.is_empty() => {
+                    self.record_usage(UsageEvent::SessionCreated);
+                    Ok(session.session_id.into())
+                }
+                _ => Err(OpenTokError::UnexpectedResponse(response_str)),
+            }
+        })
+        .await
+    }
+
+    /// Creates a new OpenTok session for `room`, an application defined
+    /// room key.
+    /// With a session store set with `OpenTokBuilder::with_session_fallback`,
+    /// new sessions are remembered, and if creating one fails with a
+    /// transient error, the last session created for the room is returned
+    /// instead, marked as degraded, provided it is recent enough.
+    /// With a lock set with `OpenTokBuilder::with_session_lock` too,
+    /// concurrent calls for the same room share a single new session, which
+    /// all but the call creating it get marked as reused.
+    #[instrument(level = "debug", skip(self, options), err)]
+    pub async fn create_session_for_room<'a>(
+        &self,
+        room: &str,
+        options: SessionOptions<'a>,
+    ) -> Result<RoomSession, OpenTokError> {
+        self.bounded(async move {
+            // The session stored for the room before waiting for its lock,
+            // to tell whether another caller created one in the meantime.
+            let before = match (&self.session_lock, &self.session_fallback) {
+                (Some(_), Some(fallback)) => fallback.store.get(room).await,
+                _ => None,
+            };
+            let _guard = match &self.session_lock {
+                Some(lock) => Some(lock.lock(room).await?),
+                None => None,
+            };
+            if let (Some(_), Some(fallback)) = (&self.session_lock, &self.session_fallback) {
+                if let Some(session) = fallback.store.get(room).await {
+                    let stored_before = before.map(|before| before.session_id);
+                    if stored_before.as_ref() != Some(&session.session_id) {
+                        return Ok(RoomSession {
+                            session_id: session.session_id,
+                            reused: true,
+                            degraded: false,
+                            created_at: session.created_at,
+                        });
+                    }
+                }
+            }
+            let result = self.create_session(options).await;
+            let fallback = match &self.session_fallback {
+                Some(fallback) => fallback,
+                None => {
+                    return result.map(|session_id| RoomSession {
+                        session_id,
+                        reused: false,
+                        degraded: false,
+                        created_at: self.time_source.now(),
+                    })
+                }
+            };
+            match result {
+                Ok(session_id) => {
+                    let created_at = self.time_source.now();
+                    let session = StoredSession {
+                        session_id: session_id.clone(),
+                        created_at,
+                    };
+                    fallback.store.put(room, session).await;
+                    Ok(RoomSession {
+                        session_id,
+                        reused: false,
+                        degraded: false,
+                        created_at,
+                    })
+                }
+                Err(error) if error.is_transient() => match fallback.store.get(room).await {
+                    Some(session)
+                        if self
+                            .time_source
+                            .now()
+                            .duration_since(session.created_at)
+                            .unwrap_or_default()
+                            <= fallback.max_age =>
+                    {
+                        Ok(RoomSession {
+                            session_id: session.session_id,
+                            reused: true,
+                            degraded: true,
+                            created_at: session.created_at,
+                        })
+                    }
+                    _ => Err(error),
+                },
+                Err(error) => Err(error),
+            }
+        })
+        .await
+    }
+
+    /// The failed requests sampled as configured with
+    /// `OpenTokBuilder::with_failure_sampling`, oldest first, with their
+    /// JWT and body redacted. Retried requests appear once per failed
+    /// attempt.
+    pub fn recent_failures(&self) -> Vec<FailedRequest> {
+        self.client.recent_failures()
+    }
+
+    /// Resolves to `future`'s output, or to `OpenTokError::Timeout` once the
+    /// deadline set with `OpenTokBuilder::with_hard_deadline` is reached.
+    async fn bounded<T>(
+        &self,
+        future: impl Future<Output = Result<T, OpenTokError>>,
+    ) -> Result<T, OpenTokError> {
+        timeout(self.client.timer(), self.hard_deadline, future).await
+    }
+
+    fn record_usage(&self, event: UsageEvent) {
+        if let Some(sink) = &self.usage_sink {
+            sink.record(&self.credentials.credentials().api_key, event);
         }
     }
 
-    pub fn generate_token(&self, session_id: &str, role: TokenRole) -> String {
-        let token_data = TokenData::new(session_id, role);
-        let signed = hmacsha1::hmac_sha1(
-            self.api_secret.as_bytes(),
-            token_data.to_string().as_bytes(),
-        )
-        .to_hex();
-        let decoded = format!("partner_id={}&sig={}:{}", self.api_key, signed, token_data);
-        let encoded = base64::encode(decoded);
-        format!("T1=={}", encoded)
+    /// Decodes `session_id`, checking that it is a well formed session ID
+    /// of this instance's project, for instance before generating a token
+    /// for a session ID received from a client.
+    /// Fails with `OpenTokError::BadRequest` otherwise.
+    pub fn validate_session_id(&self, session_id: &str) -> Result<ParsedSessionId, OpenTokError> {
+        let parsed = ParsedSessionId::parse(session_id)?;
+        if parsed.api_key != self.credentials.credentials().api_key {
+            return Err(OpenTokError::BadRequest(format!(
+                "Session ID {} belongs to another project",
+                session_id
+            )));
+        }
+        Ok(parsed)
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub fn generate_token(&self, session_id: &SessionId, role: TokenRole) -> String {
+        let token = self.local_minter.sign(session_id, role);
+        self.record_usage(UsageEvent::TokenGenerated);
+        token
+    }
+
+    /// Mints a token for `session_id` with the `TokenMinter` set with
+    /// `OpenTokBuilder::with_token_minter`, or signs it locally like
+    /// `generate_token` if none was set.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn mint_token(
+        &self,
+        session_id: &SessionId,
+        role: TokenRole,
+    ) -> Result<String, OpenTokError> {
+        self.bounded(async move {
+            let token = match &self.token_minter {
+                Some(minter) => minter.mint(session_id, role).await?,
+                None => self.local_minter.sign(session_id, role),
+            };
+            self.record_usage(UsageEvent::TokenGenerated);
+            Ok(token)
+        })
+        .await
     }
 
+    #[instrument(level = "debug", skip(self), err)]
     pub async fn get_stream_info(
         &self,
-        session_id: &str,
-        stream_id: &str,
+        session_id: &SessionId,
+        stream_id: &StreamId,
     ) -> Result<StreamInfo, OpenTokError> {
-        let endpoint = format!(
-            "{}{}{}/session/{}/stream/{}",
-            SERVER_URL, API_ENDPOINT_PATH_START, self.api_key, session_id, stream_id
-        );
-        let mut response = http_client::get(&endpoint, &self.api_key, &self.api_secret).await?;
-        let response_str = response.body_string().await?;
-        serde_json::from_str::<StreamInfo>(&response_str)
-            .map_err(|_| OpenTokError::UnexpectedResponse(response_str.clone()))
+        self.bounded(async move {
+            let credentials = self.credentials.credentials();
+            let endpoint = Endpoint::project(&credentials.api_key)
+                .segment("session")
+                .segment(session_id.as_str())
+                .segment("stream")
+                .segment(stream_id.as_str());
+            let response_str = self
+                .client
+                .get(&endpoint, self.credentials.as_ref())
+                .await?;
+            serde_json::from_str::<StreamInfo>(&response_str)
+                .map_err(|_| OpenTokError::UnexpectedResponse(response_str.clone()))
+        })
+        .await
+    }
+
+    /// Checks whether the OpenTok API is reachable with the configured
+    /// credentials, measuring the latency of an authenticated request and
+    /// the skew between the local and the server clocks.
+    /// Meant to back readiness probes, so it never fails: errors are
+    /// reported through the returned `Health` status.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn health(&self) -> Health {
+        let credentials = self.credentials.credentials();
+        let endpoint = Endpoint::project(&credentials.api_key)
+            .segment("archive")
+            .query("count", "1");
+        let start = Instant::now();
+        let response = self
+            .bounded(
+                self.client
+                    .get_unchecked(&endpoint, self.credentials.as_ref()),
+            )
+            .await;
+        let latency = start.elapsed();
+        let clock_skew = response
+            .as_ref()
+            .ok()
+            .and_then(|response| response.header("Date"))
+            .and_then(|date| httpdate::parse_http_date(date).ok())
+            .map(|server_time| ClockSkew::new(self.time_source.now(), server_time))
+            .unwrap_or(ClockSkew::Unknown);
+        let status = match response.and_then(|response| self.client.check_status(response)) {
+            Ok(_) => HealthStatus::Healthy,
+            Err(error) => HealthStatus::Unhealthy(error),
+        };
+        Health {
+            status,
+            latency,
+            clock_skew,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{CannedClient, FixedTime};
 
     use futures::executor::LocalPool;
     use opentok::utils::common::Credentials;
     use opentok::utils::publisher::Publisher;
     use std::env;
 
+    #[cfg(not(any(feature = "runtime-tokio", feature = "reqwest-client")))]
     #[test]
     fn test_create_session_invalid_credentials() {
         let opentok = OpenTok::new("sancho".into(), "quijote".into());
@@ -288,6 +732,225 @@ mod tests {
             .is_err());
     }
 
+    #[cfg(any(feature = "runtime-tokio", feature = "reqwest-client"))]
+    #[tokio::test]
+    async fn test_create_session_invalid_credentials() {
+        let opentok = OpenTok::new("sancho".into(), "quijote".into());
+        assert!(opentok
+            .create_session(SessionOptions::default())
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_session_options_builder() {
+        let options = SessionOptions::builder()
+            .location("192.0.2.1")
+            .media_mode(MediaMode::Routed)
+            .archive_mode(ArchiveMode::Always)
+            .build()
+            .unwrap();
+        assert_eq!(options.location, Some("192.0.2.1"));
+        assert_eq!(options.media_mode, Some(MediaMode::Routed));
+
+        assert!(SessionOptions::builder()
+            .media_mode(MediaMode::Relayed)
+            .archive_mode(ArchiveMode::Always)
+            .build()
+            .is_err());
+        assert!(SessionOptions::builder()
+            .location("la mancha")
+            .build()
+            .is_err());
+
+        let opentok = OpenTok::new("sancho".into(), "quijote".into());
+        let mut pool = LocalPool::new();
+        let options = SessionOptions {
+            media_mode: Some(MediaMode::Relayed),
+            archive_mode: Some(ArchiveMode::Always),
+            ..Default::default()
+        };
+        assert!(matches!(
+            pool.run_until(opentok.create_session(options)),
+            Err(OpenTokError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_session_options_presets() {
+        let body = |options: SessionOptions<'static>| {
+            options.validate().unwrap();
+            serde_urlencoded::to_string(CreateSessionBody::from(options)).unwrap()
+        };
+        assert_eq!(
+            body(SessionOptions::one_to_one()),
+            "archiveMode=manual&p2p.preference=disabled"
+        );
+        assert_eq!(
+            body(SessionOptions::webinar()),
+            "archiveMode=always&p2p.preference=disabled"
+        );
+        assert_eq!(
+            body(SessionOptions {
+                location: Some("192.0.2.1"),
+                ..SessionOptions::webinar()
+            }),
+            "archiveMode=always&location=192.0.2.1&p2p.preference=disabled"
+        );
+        assert_eq!(
+            body(SessionOptions::p2p_calling()),
+            "archiveMode=manual&p2p.preference=enabled"
+        );
+    }
+
+    #[test]
+    fn test_stream_info_serialization() {
+        let json =
+            r#"{"id":"8b73","videoType":"screen","name":"Sancho","layoutClassList":["full"]}"#;
+        let stream_info: StreamInfo = serde_json::from_str(json).unwrap();
+        assert_eq!(stream_info.id, StreamId::from("8b73"));
+        assert_eq!(stream_info.video_type, VideoType::Screen);
+        assert_eq!(stream_info.name, "Sancho");
+        assert_eq!(stream_info.layout_class_list, vec!["full"]);
+        assert_eq!(serde_json::to_string(&stream_info).unwrap(), json);
+    }
+
+    #[test]
+    fn test_validate_session_id() {
+        let opentok = OpenTok::new("12345678".into(), "quijote".into());
+        let session_id =
+            "1_MX4xMjM0NTY3OH5-MTYzNDU2Nzg5MDEyM35hYmNkZWZnaGlqa2xtbm9wcXJzdHV2d3h5en5-fg";
+        assert_eq!(
+            opentok
+                .validate_session_id(session_id)
+                .map(|parsed| parsed.api_key),
+            Ok("12345678".into())
+        );
+
+        let opentok = OpenTok::new("87654321".into(), "quijote".into());
+        assert!(matches!(
+            opentok.validate_session_id(session_id),
+            Err(OpenTokError::BadRequest(_))
+        ));
+    }
+
+    struct FixedNonce;
+
+    impl NonceSource for FixedNonce {
+        fn nonce(&self) -> u64 {
+            42
+        }
+    }
+
+    #[test]
+    fn test_deterministic_token() {
+        let time = time::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let opentok = OpenTok::builder("12345".into(), "quijote".into())
+            .with_time_source(FixedTime(time))
+            .with_nonce_source(FixedNonce)
+            .build();
+        let session_id = SessionId::from("1_MX4");
+        let token = opentok.generate_token(&session_id, TokenRole::Publisher);
+        assert_eq!(
+            token,
+            opentok.generate_token(&session_id, TokenRole::Publisher)
+        );
+        let decoded = String::from_utf8(base64::decode(&token[4..]).unwrap()).unwrap();
+        assert!(decoded.ends_with(
+            "session_id=1_MX4&create_time=1600000000&expire_time=1600086400&nonce=42&role=publisher"
+        ));
+
+        let opentok = OpenTok::builder("12345".into(), "quijote".into())
+            .with_time_source(FixedTime(time))
+            .with_token_clock_skew(Duration::from_secs(30))
+            .build();
+        let token = opentok.generate_token(&session_id, TokenRole::Publisher);
+        let decoded = String::from_utf8(base64::decode(&token[4..]).unwrap()).unwrap();
+        assert!(decoded.contains("&create_time=1599999970&expire_time=1600086400&"));
+
+        let opentok = OpenTok::builder("12345".into(), "quijote".into())
+            .with_time_source(FixedTime(time))
+            .with_nonce_source(FixedNonce)
+            .with_token_clock_skew(Duration::from_secs(30))
+            .build();
+        let token = opentok.generate_token(&session_id, TokenRole::Publisher);
+        assert_eq!(
+            futures::executor::block_on(opentok.mint_token(&session_id, TokenRole::Publisher)),
+            Ok(token.clone())
+        );
+        let minter = LocalTokenMinter::new("12345".into(), "quijote".into())
+            .with_time_source(FixedTime(time))
+            .with_nonce_source(FixedNonce)
+            .with_clock_skew(Duration::from_secs(30));
+        assert_eq!(
+            futures::executor::block_on(minter.mint(&session_id, TokenRole::Publisher)),
+            Ok(token)
+        );
+
+        let before_epoch = time::UNIX_EPOCH - Duration::from_secs(1);
+        let opentok = OpenTok::builder("12345".into(), "quijote".into())
+            .with_time_source(FixedTime(before_epoch))
+            .build();
+        let token = opentok.generate_token(&session_id, TokenRole::Publisher);
+        let decoded = String::from_utf8(base64::decode(&token[4..]).unwrap()).unwrap();
+        assert!(decoded.contains("&create_time=0&"));
+    }
+
+    struct HangingClient;
+
+    #[async_trait::async_trait]
+    impl HttpClient for HangingClient {
+        async fn send(&self, _: HttpRequest) -> Result<HttpResponse, OpenTokError> {
+            futures::future::pending().await
+        }
+    }
+
+    #[test]
+    fn test_hard_deadline() {
+        let opentok = OpenTok::builder("sancho".into(), "quijote".into())
+            .with_http_client(HangingClient)
+            .with_timer(FuturesTimer)
+            .with_hard_deadline(Duration::from_millis(10))
+            .build();
+        assert_eq!(
+            futures::executor::block_on(opentok.create_session(SessionOptions::default())),
+            Err(OpenTokError::Timeout)
+        );
+        let health = futures::executor::block_on(opentok.health());
+        assert_eq!(
+            health.status,
+            HealthStatus::Unhealthy(OpenTokError::Timeout)
+        );
+    }
+
+    #[test]
+    fn test_create_session_unexpected_response() {
+        for body in &[
+            "[]",
+            r#"[{"session_id":"1_MX4"},{"session_id":"1_MX5"}]"#,
+            r#"{"session_id":"1_MX4"}"#,
+            "\u{0}\u{ff}",
+        ] {
+            let opentok = OpenTok::builder("sancho".into(), "quijote".into())
+                .with_http_client(CannedClient::new(200, body))
+                .build();
+            assert!(matches!(
+                futures::executor::block_on(opentok.create_session(SessionOptions::default())),
+                Err(OpenTokError::UnexpectedResponse(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_get_stream_info_invalid_stream_id() {
+        let opentok = OpenTok::new("sancho".into(), "quijote".into());
+        let mut pool = LocalPool::new();
+        assert!(matches!(
+            pool.run_until(opentok.get_stream_info(&"session".into(), &"..".into())),
+            Err(OpenTokError::BadRequest(_))
+        ));
+    }
+
     #[test]
     fn test_create_session() {
         let api_key = env::var("OPENTOK_KEY").unwrap();
@@ -297,7 +960,79 @@ mod tests {
         let session_id = pool
             .run_until(opentok.create_session(SessionOptions::default()))
             .unwrap();
-        assert!(!session_id.is_empty());
+        assert!(!session_id.as_str().is_empty());
+        assert!(opentok.validate_session_id(session_id.as_str()).is_ok());
+    }
+
+    #[test]
+    fn test_warm_up() {
+        let api_key = env::var("OPENTOK_KEY").unwrap();
+        let api_secret = env::var("OPENTOK_SECRET").unwrap();
+        let opentok = OpenTok::new(api_key, api_secret);
+        let mut pool = LocalPool::new();
+        pool.run_until(opentok.warm_up()).unwrap();
+        let session_id = pool
+            .run_until(opentok.create_session(SessionOptions::default()))
+            .unwrap();
+        assert!(!session_id.as_str().is_empty());
+    }
+
+    #[test]
+    fn test_create_session_with_retry_policy() {
+        let api_key = env::var("OPENTOK_KEY").unwrap();
+        let api_secret = env::var("OPENTOK_SECRET").unwrap();
+        let opentok = OpenTok::builder(api_key, api_secret)
+            .with_retry_policy(RetryPolicy::default())
+            .build();
+        let mut pool = LocalPool::new();
+        let session_id = pool
+            .run_until(opentok.create_session(SessionOptions::default()))
+            .unwrap();
+        assert!(!session_id.as_str().is_empty());
+    }
+
+    #[test]
+    fn test_create_session_timeout() {
+        let api_key = env::var("OPENTOK_KEY").unwrap();
+        let api_secret = env::var("OPENTOK_SECRET").unwrap();
+        let opentok = OpenTok::builder(api_key, api_secret)
+            .with_timeouts(Timeouts {
+                connect: Some(Duration::from_secs(5)),
+                total: Some(Duration::from_secs(10)),
+            })
+            .build();
+        let mut pool = LocalPool::new();
+        let session_id = pool
+            .run_until(opentok.create_session(SessionOptions::default()))
+            .unwrap();
+        assert!(!session_id.as_str().is_empty());
+        assert_eq!(
+            pool.run_until(
+                opentok
+                    .with_timeout(Duration::from_millis(1))
+                    .create_session(SessionOptions::default())
+            ),
+            Err(OpenTokError::Timeout)
+        );
+    }
+
+    #[cfg(feature = "runtime-tokio")]
+    #[tokio::test]
+    async fn test_create_session_tokio() {
+        let api_key = env::var("OPENTOK_KEY").unwrap();
+        let api_secret = env::var("OPENTOK_SECRET").unwrap();
+        let opentok = OpenTok::builder(api_key, api_secret)
+            .with_retry_policy(RetryPolicy::default())
+            .with_timeouts(Timeouts {
+                connect: Some(Duration::from_secs(5)),
+                total: Some(Duration::from_secs(10)),
+            })
+            .build();
+        let session_id = opentok
+            .create_session(SessionOptions::default())
+            .await
+            .unwrap();
+        assert!(!session_id.as_str().is_empty());
     }
 
     #[test]
@@ -309,7 +1044,7 @@ mod tests {
         let session_id = pool
             .run_until(opentok.create_session(SessionOptions::default()))
             .unwrap();
-        assert!(!session_id.is_empty());
+        assert!(!session_id.as_str().is_empty());
         let token = opentok.generate_token(&session_id, TokenRole::Publisher);
         assert!(!token.is_empty());
     }
@@ -323,7 +1058,7 @@ mod tests {
         let session_id = pool
             .run_until(opentok.create_session(SessionOptions::default()))
             .unwrap();
-        assert!(!session_id.is_empty());
+        assert!(!session_id.as_str().is_empty());
         let token = opentok.generate_token(&session_id, TokenRole::Publisher);
         assert!(!token.is_empty());
 
@@ -332,10 +1067,11 @@ mod tests {
         let publisher = Publisher::new(
             Credentials {
                 api_key,
-                session_id: session_id.clone(),
+                session_id: session_id.to_string(),
                 token,
             },
             Some(Box::new(move |publisher, stream_id| {
+                let stream_id = StreamId::from(stream_id);
                 let mut pool = LocalPool::new();
                 let stream_info = pool
                     .run_until(opentok.get_stream_info(&session_id, &stream_id))
@@ -350,4 +1086,15 @@ mod tests {
 
         opentok::deinit().unwrap();
     }
+
+    #[test]
+    fn test_health() {
+        let api_key = env::var("OPENTOK_KEY").unwrap();
+        let api_secret = env::var("OPENTOK_SECRET").unwrap();
+        let opentok = OpenTok::new(api_key, api_secret);
+        let mut pool = LocalPool::new();
+        let health = pool.run_until(opentok.health());
+        assert_eq!(health.status, HealthStatus::Healthy);
+        assert_ne!(health.clock_skew, ClockSkew::Unknown);
+    }
 }
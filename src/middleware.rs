@@ -0,0 +1,175 @@
+use crate::{HttpClient, HttpRequest, HttpResponse, OpenTokError};
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Interceptor of the requests sent to the OpenTok API, set with
+/// `OpenTokBuilder::with_middleware`.
+/// A middleware can inspect and modify the request before handing it to
+/// `next`, inspect and modify the response `next` returns, or answer the
+/// request itself without calling `next` at all.
+/// Middlewares run for every attempt of every request, including retries.
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    async fn handle(
+        &self,
+        request: HttpRequest,
+        next: Next<'_>,
+    ) -> Result<HttpResponse, OpenTokError>;
+}
+
+/// The rest of the middleware chain, ending with the HTTP client.
+pub struct Next<'a> {
+    client: &'a dyn HttpClient,
+    middlewares: &'a [Arc<dyn Middleware>],
+}
+
+impl<'a> Next<'a> {
+    /// Hands `request` to the next middleware, or sends it if there are
+    /// none left.
+    pub async fn run(self, request: HttpRequest) -> Result<HttpResponse, OpenTokError> {
+        match self.middlewares.split_first() {
+            Some((middleware, middlewares)) => {
+                let next = Next {
+                    client: self.client,
+                    middlewares,
+                };
+                middleware.handle(request, next).await
+            }
+            None => self.client.send(request).await,
+        }
+    }
+}
+
+/// `HttpClient` running requests through middlewares, in the order they
+/// were added, before sending them with `client`.
+pub(crate) struct MiddlewareClient {
+    client: Arc<dyn HttpClient>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+}
+
+impl MiddlewareClient {
+    pub fn new(client: Arc<dyn HttpClient>, middlewares: Vec<Arc<dyn Middleware>>) -> Self {
+        Self {
+            client,
+            middlewares,
+        }
+    }
+}
+
+#[async_trait]
+impl HttpClient for MiddlewareClient {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, OpenTokError> {
+        Next {
+            client: self.client.as_ref(),
+            middlewares: &self.middlewares,
+        }
+        .run(request)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpMethod;
+
+    use futures::executor::block_on;
+    use std::sync::Mutex;
+
+    /// `HttpClient` answering every request with the headers it was sent.
+    struct EchoClient;
+
+    #[async_trait]
+    impl HttpClient for EchoClient {
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse, OpenTokError> {
+            Ok(HttpResponse {
+                status: 200,
+                headers: request.headers,
+                body: vec![],
+            })
+        }
+    }
+
+    struct AddHeader(&'static str);
+
+    #[async_trait]
+    impl Middleware for AddHeader {
+        async fn handle(
+            &self,
+            mut request: HttpRequest,
+            next: Next<'_>,
+        ) -> Result<HttpResponse, OpenTokError> {
+            request.headers.push(("X-Test".into(), self.0.into()));
+            next.run(request).await
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordStatus(Mutex<Vec<u16>>);
+
+    #[async_trait]
+    impl Middleware for RecordStatus {
+        async fn handle(
+            &self,
+            request: HttpRequest,
+            next: Next<'_>,
+        ) -> Result<HttpResponse, OpenTokError> {
+            let response = next.run(request).await?;
+            self.0.lock().unwrap().push(response.status);
+            Ok(response)
+        }
+    }
+
+    struct Stub;
+
+    #[async_trait]
+    impl Middleware for Stub {
+        async fn handle(&self, _: HttpRequest, _: Next<'_>) -> Result<HttpResponse, OpenTokError> {
+            Ok(HttpResponse {
+                status: 204,
+                headers: vec![],
+                body: vec![],
+            })
+        }
+    }
+
+    fn request() -> HttpRequest {
+        HttpRequest {
+            method: HttpMethod::Get,
+            url: "https://api.opentok.com".into(),
+            headers: vec![],
+            body: None,
+        }
+    }
+
+    #[test]
+    fn test_middlewares() {
+        let record = Arc::new(RecordStatus::default());
+        let client = MiddlewareClient::new(
+            Arc::new(EchoClient),
+            vec![
+                record.clone(),
+                Arc::new(AddHeader("a")),
+                Arc::new(AddHeader("b")),
+            ],
+        );
+        let response = block_on(client.send(request())).unwrap();
+        assert_eq!(
+            response.headers,
+            vec![
+                ("X-Test".to_string(), "a".to_string()),
+                ("X-Test".to_string(), "b".to_string()),
+            ]
+        );
+        assert_eq!(*record.0.lock().unwrap(), vec![200]);
+
+        let client = MiddlewareClient::new(
+            Arc::new(EchoClient),
+            vec![record.clone(), Arc::new(Stub), Arc::new(AddHeader("a"))],
+        );
+        let response = block_on(client.send(request())).unwrap();
+        assert!(response.headers.is_empty());
+        assert_eq!(*record.0.lock().unwrap(), vec![200, 204]);
+    }
+}
@@ -0,0 +1,277 @@
+//! Programmable implementation of `OpenTokApi`, for testing code using this
+//! crate without credentials nor network access.
+
+use crate::time::SystemTime;
+use crate::{
+    ClockSkew, Health, HealthStatus, OpenTokApi, OpenTokError, ParsedSessionId, RoomSession,
+    SessionId, SessionOptions, StreamId, StreamInfo, TokenRole,
+};
+
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+/// Call made to a `MockOpenTok`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MockCall {
+    WarmUp,
+    CreateSession,
+    CreateSessionForRoom {
+        room: String,
+    },
+    ValidateSessionId {
+        session_id: String,
+    },
+    GenerateToken {
+        session_id: SessionId,
+        role: TokenRole,
+    },
+    MintToken {
+        session_id: SessionId,
+        role: TokenRole,
+    },
+    GetStreamInfo {
+        session_id: SessionId,
+        stream_id: StreamId,
+    },
+    Health,
+}
+
+/// `OpenTokApi` implementation recording the calls made to it and
+/// answering them with the responses pushed beforehand, in order.
+/// When no response was pushed for an operation, it succeeds with a
+/// made up session ID, a healthy status and so on, except for
+/// `get_stream_info`, which fails with `OpenTokError::NotFound`, and
+/// `validate_session_id`, which only decodes the session ID.
+#[derive(Debug, Default)]
+pub struct MockOpenTok {
+    calls: Mutex<Vec<MockCall>>,
+    sessions: Mutex<VecDeque<Result<SessionId, OpenTokError>>>,
+    stream_infos: Mutex<VecDeque<Result<StreamInfo, OpenTokError>>>,
+    validations: Mutex<VecDeque<Result<ParsedSessionId, OpenTokError>>>,
+    warm_ups: Mutex<VecDeque<Result<(), OpenTokError>>>,
+    health_statuses: Mutex<VecDeque<HealthStatus>>,
+}
+
+impl MockOpenTok {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls made so far, in order.
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.calls
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Queues the result of a `create_session` or `create_session_for_room`
+    /// call.
+    pub fn push_session(&self, result: Result<SessionId, OpenTokError>) {
+        self.sessions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push_back(result);
+    }
+
+    /// Queues the result of a `get_stream_info` call.
+    pub fn push_stream_info(&self, result: Result<StreamInfo, OpenTokError>) {
+        self.stream_infos
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push_back(result);
+    }
+
+    /// Queues the result of a `validate_session_id` call.
+    pub fn push_validation(&self, result: Result<ParsedSessionId, OpenTokError>) {
+        self.validations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push_back(result);
+    }
+
+    /// Queues the result of a `warm_up` call.
+    pub fn push_warm_up(&self, result: Result<(), OpenTokError>) {
+        self.warm_ups
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push_back(result);
+    }
+
+    /// Queues the status reported by a `health` call.
+    pub fn push_health(&self, status: HealthStatus) {
+        self.health_statuses
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push_back(status);
+    }
+
+    fn record(&self, call: MockCall) -> usize {
+        let mut calls = self.calls.lock().unwrap_or_else(PoisonError::into_inner);
+        calls.push(call);
+        calls.len()
+    }
+
+    fn next_session(&self, call: usize) -> Result<SessionId, OpenTokError> {
+        self.sessions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop_front()
+            .unwrap_or_else(|| Ok(format!("mock_session_{}", call).into()))
+    }
+}
+
+#[async_trait]
+impl OpenTokApi for MockOpenTok {
+    async fn warm_up(&self) -> Result<(), OpenTokError> {
+        self.record(MockCall::WarmUp);
+        self.warm_ups
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop_front()
+            .unwrap_or(Ok(()))
+    }
+
+    async fn create_session<'a>(
+        &self,
+        _options: SessionOptions<'a>,
+    ) -> Result<SessionId, OpenTokError> {
+        let call = self.record(MockCall::CreateSession);
+        self.next_session(call)
+    }
+
+    async fn create_session_for_room<'a>(
+        &self,
+        room: &str,
+        _options: SessionOptions<'a>,
+    ) -> Result<RoomSession, OpenTokError> {
+        let call = self.record(MockCall::CreateSessionForRoom { room: room.into() });
+        self.next_session(call).map(|session_id| RoomSession {
+            session_id,
+            reused: false,
+            degraded: false,
+            created_at: SystemTime::now(),
+        })
+    }
+
+    fn validate_session_id(&self, session_id: &str) -> Result<ParsedSessionId, OpenTokError> {
+        self.record(MockCall::ValidateSessionId {
+            session_id: session_id.into(),
+        });
+        self.validations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop_front()
+            .unwrap_or_else(|| ParsedSessionId::parse(session_id))
+    }
+
+    fn generate_token(&self, session_id: &SessionId, role: TokenRole) -> String {
+        let token = format!("mock_token_{}_{}", session_id, role);
+        self.record(MockCall::GenerateToken {
+            session_id: session_id.clone(),
+            role,
+        });
+        token
+    }
+
+    async fn mint_token(
+        &self,
+        session_id: &SessionId,
+        role: TokenRole,
+    ) -> Result<String, OpenTokError> {
+        let token = format!("mock_token_{}_{}", session_id, role);
+        self.record(MockCall::MintToken {
+            session_id: session_id.clone(),
+            role,
+        });
+        Ok(token)
+    }
+
+    async fn get_stream_info(
+        &self,
+        session_id: &SessionId,
+        stream_id: &StreamId,
+    ) -> Result<StreamInfo, OpenTokError> {
+        self.record(MockCall::GetStreamInfo {
+            session_id: session_id.clone(),
+            stream_id: stream_id.clone(),
+        });
+        self.stream_infos
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop_front()
+            .unwrap_or_else(|| Err(OpenTokError::NotFound(stream_id.to_string())))
+    }
+
+    async fn health(&self) -> Health {
+        self.record(MockCall::Health);
+        Health {
+            status: self
+                .health_statuses
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .pop_front()
+                .unwrap_or(HealthStatus::Healthy),
+            latency: Duration::from_secs(0),
+            clock_skew: ClockSkew::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::executor::block_on;
+
+    async fn start_call(opentok: &dyn OpenTokApi) -> Result<String, OpenTokError> {
+        let session_id = opentok.create_session(SessionOptions::default()).await?;
+        Ok(opentok.generate_token(&session_id, TokenRole::Publisher))
+    }
+
+    #[test]
+    fn test_mock() {
+        let mock = MockOpenTok::new();
+        mock.push_session(Err(OpenTokError::Timeout));
+
+        assert_eq!(block_on(start_call(&mock)), Err(OpenTokError::Timeout));
+        assert_eq!(
+            block_on(start_call(&mock)),
+            Ok("mock_token_mock_session_2_publisher".into())
+        );
+        assert!(
+            block_on(mock.get_stream_info(&"mock_session_2".into(), &"stream".into())).is_err()
+        );
+        assert!(block_on(mock.health()).is_ready());
+        assert!(matches!(
+            mock.validate_session_id("mock_session_2"),
+            Err(OpenTokError::BadRequest(_))
+        ));
+        assert_eq!(
+            mock.calls(),
+            vec![
+                MockCall::CreateSession,
+                MockCall::CreateSession,
+                MockCall::GenerateToken {
+                    session_id: "mock_session_2".into(),
+                    role: TokenRole::Publisher,
+                },
+                MockCall::GetStreamInfo {
+                    session_id: "mock_session_2".into(),
+                    stream_id: "stream".into(),
+                },
+                MockCall::Health,
+                MockCall::ValidateSessionId {
+                    session_id: "mock_session_2".into(),
+                },
+            ]
+        );
+        mock.push_validation(Err(OpenTokError::Timeout));
+        assert_eq!(
+            mock.validate_session_id("1_MX4"),
+            Err(OpenTokError::Timeout)
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use crate::OpenTokError;
+
+use futures::stream::{self, Stream, StreamExt};
+use futures::Future;
+use serde::Deserialize;
+
+/// Page of items returned by the list endpoints of the OpenTok API.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Page<T> {
+    /// Total number of items, across all pages.
+    pub count: usize,
+    pub items: Vec<T>,
+}
+
+struct PaginationState<F> {
+    fetch: F,
+    offset: usize,
+    done: bool,
+}
+
+/// Turns a list endpoint into a stream of its items.
+/// `fetch` is called with an offset and a page size to request each page
+/// in turn, as items are consumed, until all `Page::count` items were
+/// returned or a page comes back empty. The stream ends after yielding
+/// the first error.
+pub fn paginate<T, F, Fut>(
+    page_size: usize,
+    fetch: F,
+) -> impl Stream<Item = Result<T, OpenTokError>>
+where
+    F: FnMut(usize, usize) -> Fut,
+    Fut: Future<Output = Result<Page<T>, OpenTokError>>,
+{
+    let state = PaginationState {
+        fetch,
+        offset: 0,
+        done: false,
+    };
+    stream::unfold(state, move |mut state| async move {
+        if state.done {
+            return None;
+        }
+        let items = match (state.fetch)(state.offset, page_size).await {
+            Ok(page) => {
+                state.offset += page.items.len();
+                state.done = page.items.is_empty() || state.offset >= page.count;
+                page.items.into_iter().map(Ok).collect()
+            }
+            Err(error) => {
+                state.done = true;
+                vec![Err(error)]
+            }
+        };
+        Some((stream::iter(items), state))
+    })
+    .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::executor::block_on;
+    use futures::future;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_paginate() {
+        let requests = RefCell::new(vec![]);
+        let items = block_on(
+            paginate(10, |offset, count| {
+                requests.borrow_mut().push((offset, count));
+                let items = (offset..(offset + count).min(25)).collect();
+                future::ready(Ok(Page { count: 25, items }))
+            })
+            .collect::<Vec<_>>(),
+        );
+        assert_eq!(items, (0..25).map(Ok).collect::<Vec<_>>());
+        assert_eq!(*requests.borrow(), vec![(0, 10), (10, 10), (20, 10)]);
+    }
+
+    #[test]
+    fn test_paginate_error() {
+        let items = block_on(
+            paginate(10, |offset, count| {
+                future::ready(match offset {
+                    0 => Ok(Page {
+                        count: 25,
+                        items: (offset..offset + count).collect(),
+                    }),
+                    _ => Err(OpenTokError::Timeout),
+                })
+            })
+            .collect::<Vec<_>>(),
+        );
+        assert_eq!(items.len(), 11);
+        assert_eq!(items[10], Err(OpenTokError::Timeout));
+    }
+
+    #[test]
+    fn test_paginate_empty() {
+        let items = block_on(
+            paginate(10, |_, _| {
+                future::ready(Ok(Page::<u32> {
+                    count: 0,
+                    items: vec![],
+                }))
+            })
+            .collect::<Vec<_>>(),
+        );
+        assert!(items.is_empty());
+    }
+}
@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+/// Configuration of the connections the default HTTP backends keep open
+/// to the OpenTok servers, so that bursts of requests reuse them instead
+/// of paying a TLS handshake each. Settings left to `None` are up to the
+/// backend.
+#[derive(Clone, Copy, Debug)]
+pub struct PoolConfig {
+    /// How long an idle connection is kept open for reuse.
+    pub idle_timeout: Option<Duration>,
+    /// Maximum number of idle connections kept open. reqwest applies it
+    /// per host, surf to all hosts together.
+    pub max_idle_connections: Option<usize>,
+    /// Interval of the TCP keep-alive probes sent on open connections.
+    pub tcp_keepalive: Option<Duration>,
+}
+
+impl Default for PoolConfig {
+    /// Idle connections are kept for 90 seconds, with TCP keep-alive probes
+    /// every 60 seconds, and their number is up to the backend.
+    fn default() -> Self {
+        Self {
+            idle_timeout: Some(Duration::from_secs(90)),
+            max_idle_connections: None,
+            tcp_keepalive: Some(Duration::from_secs(60)),
+        }
+    }
+}
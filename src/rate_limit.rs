@@ -0,0 +1,101 @@
+use crate::time::Instant;
+use crate::Timer;
+
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+/// Client-side limit of the rate of requests sent to the OpenTok API, set
+/// with `OpenTokBuilder::with_rate_limit`, so that bulk operations stay
+/// under the project's rate limits instead of getting rejected with 429
+/// responses. Requests over the limit are delayed until they fit in it,
+/// in the order they were made.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    /// Sustained number of requests sent per second.
+    pub requests_per_second: u32,
+    /// Number of requests that can be sent at once after a quiet period.
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Limit of `requests_per_second`, allowing bursts of as many requests.
+    pub fn per_second(requests_per_second: u32) -> Self {
+        Self {
+            requests_per_second,
+            burst: requests_per_second,
+        }
+    }
+}
+
+/// Implementation of a `RateLimit` with the generic cell rate algorithm:
+/// every request reserves the next slot, which it waits for if needed.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    interval: Duration,
+    /// How far ahead of the current time slots can be reserved without
+    /// waiting.
+    tolerance: Duration,
+    /// Theoretical arrival time of the next request.
+    next_slot: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        let interval = Duration::from_secs(1) / limit.requests_per_second.max(1);
+        Self {
+            interval,
+            tolerance: interval * limit.burst.max(1).saturating_sub(1),
+            next_slot: Mutex::new(None),
+        }
+    }
+
+    /// Reserves a slot for a request made at `now`, returning how long to
+    /// wait until it may be sent.
+    fn reserve(&self, now: Instant) -> Duration {
+        let mut next_slot = self
+            .next_slot
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let slot = next_slot.map_or(now, |slot| slot.max(now));
+        *next_slot = Some(slot + self.interval);
+        slot.saturating_duration_since(now + self.tolerance)
+    }
+
+    /// Waits until a request may be sent.
+    pub async fn acquire(&self, timer: &dyn Timer) {
+        let wait = self.reserve(Instant::now());
+        if wait > Duration::from_secs(0) {
+            timer.sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve() {
+        let limiter = RateLimiter::new(RateLimit {
+            requests_per_second: 10,
+            burst: 2,
+        });
+        let now = Instant::now();
+        let waits: Vec<_> = (0..4).map(|_| limiter.reserve(now)).collect();
+        assert_eq!(
+            waits,
+            vec![
+                Duration::from_millis(0),
+                Duration::from_millis(0),
+                Duration::from_millis(100),
+                Duration::from_millis(200),
+            ]
+        );
+
+        // Slots freed over time can be reserved again without waiting.
+        let later = now + Duration::from_secs(1);
+        assert_eq!(limiter.reserve(later), Duration::from_millis(0));
+        assert_eq!(limiter.reserve(later), Duration::from_millis(0));
+        assert_eq!(limiter.reserve(later), Duration::from_millis(100));
+    }
+}
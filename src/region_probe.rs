@@ -0,0 +1,199 @@
+use crate::http_client;
+use crate::time::{Instant, SystemTime};
+use crate::timeout::timeout;
+use crate::timer::default_timer;
+use crate::{HttpClient, HttpMethod, HttpRequest, OpenTokError, PoolConfig, Timeouts, Timer};
+
+use futures::future::join_all;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+/// Outcome of the last probe of a region.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegionLatency {
+    /// Name the region was added with.
+    pub region: String,
+    /// Round trip time of the probe, or the reason it failed.
+    pub latency: Result<Duration, OpenTokError>,
+    pub measured_at: SystemTime,
+}
+
+/// Measures the latency to regional endpoints, so that applications can
+/// pick the region closest to them, for instance to derive the location
+/// hint of new sessions or to choose where to fail over.
+///
+/// Every probe sends a HEAD request to each endpoint concurrently and times
+/// the response, whatever its status. `RegionProber::run` probes
+/// periodically, and is meant to be spawned on the application's runtime
+/// with the prober shared in an `Arc`.
+pub struct RegionProber {
+    regions: Vec<(String, String)>,
+    http: Arc<dyn HttpClient>,
+    timer: Arc<dyn Timer>,
+    timeout: Duration,
+    latencies: Mutex<Vec<RegionLatency>>,
+}
+
+impl Default for RegionProber {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegionProber {
+    /// Prober without any region, sending requests through the default HTTP
+    /// backend and timing out probes after 5 seconds.
+    pub fn new() -> Self {
+        Self {
+            regions: vec![],
+            http: http_client::default_http_client(
+                &Timeouts::default(),
+                None,
+                &PoolConfig::default(),
+            ),
+            timer: default_timer(),
+            timeout: Duration::from_secs(5),
+            latencies: Mutex::new(vec![]),
+        }
+    }
+
+    /// Probes the endpoint at `url` under the name `region`.
+    pub fn with_region(mut self, region: &str, url: &str) -> Self {
+        self.regions.push((region.into(), url.into()));
+        self
+    }
+
+    /// Sends probes through `client` instead of the default HTTP backend.
+    pub fn with_http_client(mut self, client: impl HttpClient + 'static) -> Self {
+        self.http = Arc::new(client);
+        self
+    }
+
+    /// Sleeps with `timer` between probes and for their timeouts, instead
+    /// of the timer of the runtime selected with the `runtime-*` features.
+    pub fn with_timer(mut self, timer: impl Timer + 'static) -> Self {
+        self.timer = Arc::new(timer);
+        self
+    }
+
+    /// Fails probes taking longer than `timeout` with
+    /// `OpenTokError::Timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Probes every region once, and returns the results, also kept for
+    /// `RegionProber::latencies`.
+    pub async fn probe(&self) -> Vec<RegionLatency> {
+        let probes = self.regions.iter().map(|(region, url)| async move {
+            let request = HttpRequest {
+                method: HttpMethod::Head,
+                url: url.clone(),
+                headers: vec![],
+                body: None,
+            };
+            let start = Instant::now();
+            let latency = timeout(
+                self.timer.as_ref(),
+                Some(self.timeout),
+                self.http.send(request),
+            )
+            .await
+            .map(|_| start.elapsed());
+            RegionLatency {
+                region: region.clone(),
+                latency,
+                measured_at: SystemTime::now(),
+            }
+        });
+        let latencies = join_all(probes).await;
+        *self
+            .latencies
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = latencies.clone();
+        latencies
+    }
+
+    /// Probes every region every `interval`, forever.
+    pub async fn run(&self, interval: Duration) {
+        loop {
+            self.probe().await;
+            self.timer.sleep(interval).await;
+        }
+    }
+
+    /// Results of the last probe, empty until one completes.
+    pub fn latencies(&self) -> Vec<RegionLatency> {
+        self.latencies
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// The region with the lowest latency in the last probe, if any could
+    /// be reached.
+    pub fn fastest(&self) -> Option<RegionLatency> {
+        self.latencies()
+            .into_iter()
+            .filter(|latency| latency.latency.is_ok())
+            .min_by_key(|latency| latency.latency.as_ref().ok().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FuturesTimer, HttpResponse};
+
+    use async_trait::async_trait;
+    use futures::executor::block_on;
+
+    /// `HttpClient` answering after a delay depending on the URL, and
+    /// failing for unknown hosts.
+    struct Regions;
+
+    #[async_trait]
+    impl HttpClient for Regions {
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse, OpenTokError> {
+            let delay = match request.url.as_str() {
+                "https://near.example.com" => 1,
+                "https://far.example.com" => 20,
+                "https://hung.example.com" => 1000,
+                _ => return Err(OpenTokError::ConnectionError(request.url)),
+            };
+            FuturesTimer.sleep(Duration::from_millis(delay)).await;
+            Ok(HttpResponse {
+                status: 404,
+                headers: vec![],
+                body: vec![],
+            })
+        }
+    }
+
+    #[test]
+    fn test_region_prober() {
+        let prober = RegionProber::new()
+            .with_region("far", "https://far.example.com")
+            .with_region("near", "https://near.example.com")
+            .with_region("down", "https://down.example.com")
+            .with_region("hung", "https://hung.example.com")
+            .with_http_client(Regions)
+            .with_timer(FuturesTimer)
+            .with_timeout(Duration::from_millis(200));
+        assert!(prober.latencies().is_empty());
+        assert_eq!(prober.fastest(), None);
+
+        let latencies = block_on(prober.probe());
+        let regions: Vec<_> = latencies.iter().map(|latency| &latency.region).collect();
+        assert_eq!(regions, vec!["far", "near", "down", "hung"]);
+        assert!(latencies[0].latency.as_ref().unwrap() >= &Duration::from_millis(20));
+        assert!(matches!(
+            latencies[2].latency,
+            Err(OpenTokError::ConnectionError(_))
+        ));
+        assert_eq!(latencies[3].latency, Err(OpenTokError::Timeout));
+        assert_eq!(prober.latencies(), latencies);
+        assert_eq!(prober.fastest().unwrap().region, "near");
+    }
+}
@@ -0,0 +1,76 @@
+use rand::Rng;
+use std::time::Duration;
+
+/// Policy for retrying requests that failed with a transient error:
+/// connection failures, rate limiting (429) and server errors (5xx).
+/// Retries are opt-in, see `OpenTokBuilder::with_retry_policy`.
+///
+/// Note that requests creating resources, such as `create_session`, are
+/// retried too, so a request that reached the server before failing may
+/// end up being applied twice.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// Backoff before the first retry. It is doubled for every subsequent retry.
+    pub initial_backoff: Duration,
+    /// Upper bound of the backoff between two attempts.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Exponential backoff with full jitter to wait for before retrying
+    /// after `attempt` failed attempts.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff);
+        let millis = backoff.as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(0..=millis))
+    }
+
+    /// Whether a request failed with `status` should be attempted again.
+    pub(crate) fn is_retryable(status: u16) -> bool {
+        status == 429 || (500..=599).contains(&status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(1000),
+        };
+        for _ in 0..100 {
+            assert!(policy.backoff(1) <= Duration::from_millis(100));
+            assert!(policy.backoff(3) <= Duration::from_millis(400));
+            assert!(policy.backoff(8) <= Duration::from_millis(1000));
+            assert!(policy.backoff(u32::MAX) <= Duration::from_millis(1000));
+        }
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(RetryPolicy::is_retryable(429));
+        assert!(RetryPolicy::is_retryable(503));
+        assert!(!RetryPolicy::is_retryable(400));
+        assert!(!RetryPolicy::is_retryable(404));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::OpenTokError;
+
+use async_trait::async_trait;
+use futures::lock::Mutex as AsyncMutex;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Lock held on a key of a `SessionLock`, released when dropped.
+pub struct SessionLockGuard {
+    release: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl SessionLockGuard {
+    /// Guard calling `release` when dropped. Implementations of `SessionLock`
+    /// whose release is asynchronous are expected to spawn it from there, or
+    /// to rely on the lock expiring.
+    pub fn new(release: impl FnOnce() + Send + 'static) -> Self {
+        Self {
+            release: Some(Box::new(release)),
+        }
+    }
+}
+
+impl Drop for SessionLockGuard {
+    fn drop(&mut self) {
+        if let Some(release) = self.release.take() {
+            release();
+        }
+    }
+}
+
+impl fmt::Debug for SessionLockGuard {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("SessionLockGuard").finish()
+    }
+}
+
+/// Mutual exclusion between concurrent operations on the same room or
+/// session, such as creating the session of a room or starting its archive,
+/// so that they are not performed twice.
+/// Implementations backed by a shared store, such as Redis, extend the
+/// exclusion to several instances of a service.
+#[async_trait]
+pub trait SessionLock: Send + Sync {
+    /// Waits until no one else holds the lock on `key`, then acquires it
+    /// until the returned guard is dropped.
+    async fn lock(&self, key: &str) -> Result<SessionLockGuard, OpenTokError>;
+}
+
+/// `SessionLock` excluding operations within this process only.
+#[derive(Debug, Default)]
+pub struct InMemorySessionLock {
+    locks: Arc<Mutex<HashMap<String, Arc<AsyncMutex<()>>>>>,
+}
+
+impl InMemorySessionLock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SessionLock for InMemorySessionLock {
+    async fn lock(&self, key: &str) -> Result<SessionLockGuard, OpenTokError> {
+        let mutex = self
+            .locks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(key.into())
+            .or_default()
+            .clone();
+        let guard = mutex.lock_owned().await;
+        let locks = self.locks.clone();
+        let key = key.to_string();
+        Ok(SessionLockGuard::new(move || {
+            drop(guard);
+            // Forget the key once no one holds nor waits for its lock.
+            let mut locks = locks.lock().unwrap_or_else(PoisonError::into_inner);
+            if locks.get(&key).map(Arc::strong_count) == Some(1) {
+                locks.remove(&key);
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::executor::block_on;
+    use futures::future::{self, Either};
+    use futures::pin_mut;
+
+    #[test]
+    fn test_in_memory_session_lock() {
+        let lock = InMemorySessionLock::new();
+        let guard = block_on(lock.lock("mancha")).unwrap();
+        assert!(block_on(lock.lock("toboso")).is_ok());
+
+        let second = lock.lock("mancha");
+        pin_mut!(second);
+        assert!(matches!(
+            block_on(future::select(second.as_mut(), future::ready(()))),
+            Either::Right(_)
+        ));
+
+        drop(guard);
+        let guard = block_on(second).unwrap();
+        drop(guard);
+        assert!(lock.locks.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,287 @@
+use crate::time::SystemTime;
+use crate::SessionId;
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+/// A session created for a room, as remembered by a `SessionStore`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StoredSession {
+    pub session_id: SessionId,
+    pub created_at: SystemTime,
+}
+
+/// Storage of the last session created for each room, keyed by an
+/// application defined room key.
+/// Implementations backed by a shared database let several instances of a
+/// service fall back to the same sessions.
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    /// The last session stored for `room`, if any.
+    async fn get(&self, room: &str) -> Option<StoredSession>;
+    /// Remembers `session` as the last session created for `room`.
+    async fn put(&self, room: &str, session: StoredSession);
+}
+
+/// `SessionStore` keeping sessions in memory.
+#[derive(Debug, Default)]
+pub struct InMemorySessionStore {
+    sessions: Mutex<HashMap<String, StoredSession>>,
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SessionStore for InMemorySessionStore {
+    async fn get(&self, room: &str) -> Option<StoredSession> {
+        self.sessions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(room)
+            .cloned()
+    }
+
+    async fn put(&self, room: &str, session: StoredSession) {
+        self.sessions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(room.into(), session);
+    }
+}
+
+/// Session returned by `OpenTok::create_session_for_room`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoomSession {
+    pub session_id: SessionId,
+    /// Whether the session was created earlier rather than by this call,
+    /// either by a concurrent call holding the room's lock or as the
+    /// fallback of a degraded session, so that callers can track session
+    /// churn.
+    pub reused: bool,
+    /// Whether creating a new session failed and a previously created
+    /// session was returned instead.
+    pub degraded: bool,
+    /// When the session was created, earlier than the call for a reused
+    /// session.
+    pub created_at: SystemTime,
+}
+
+impl RoomSession {
+    /// Time elapsed from the creation of the session to `now`, as read from
+    /// the `TimeSource` set with `OpenTokBuilder::with_time_source`, for
+    /// instance to alert on sessions reused for longer than expected or to
+    /// shorten the lifetime of the tokens generated for them.
+    pub fn age(&self, now: SystemTime) -> Duration {
+        now.duration_since(self.created_at).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::FixedTime;
+    use crate::time::UNIX_EPOCH;
+    use crate::{
+        FuturesTimer, HttpClient, HttpRequest, HttpResponse, InMemorySessionLock, OpenTok,
+        OpenTokError, SessionOptions, Timer,
+    };
+
+    use futures::executor::block_on;
+    use futures::join;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    struct UnreachableClient;
+
+    #[async_trait]
+    impl HttpClient for UnreachableClient {
+        async fn send(&self, _: HttpRequest) -> Result<HttpResponse, OpenTokError> {
+            Err(OpenTokError::ConnectionError("Unreachable".into()))
+        }
+    }
+
+    #[test]
+    fn test_degraded_session() {
+        let store = Arc::new(InMemorySessionStore::new());
+        let opentok = OpenTok::builder("sancho".into(), "quijote".into())
+            .with_http_client(UnreachableClient)
+            .with_session_fallback(store.clone(), Duration::from_secs(60))
+            .build();
+
+        assert_eq!(
+            block_on(opentok.create_session_for_room("mancha", SessionOptions::default())),
+            Err(OpenTokError::ConnectionError("Unreachable".into()))
+        );
+
+        let created_at = SystemTime::now() - Duration::from_secs(30);
+        block_on(store.put(
+            "mancha",
+            StoredSession {
+                session_id: "1_MX4".into(),
+                created_at,
+            },
+        ));
+        let session =
+            block_on(opentok.create_session_for_room("mancha", SessionOptions::default())).unwrap();
+        assert_eq!(
+            session,
+            RoomSession {
+                session_id: "1_MX4".into(),
+                reused: true,
+                degraded: true,
+                created_at,
+            }
+        );
+        assert!(session.age(SystemTime::now()) >= Duration::from_secs(30));
+        assert!(
+            block_on(opentok.create_session_for_room("toboso", SessionOptions::default())).is_err()
+        );
+
+        block_on(store.put(
+            "mancha",
+            StoredSession {
+                session_id: "1_MX4".into(),
+                created_at: SystemTime::now() - Duration::from_secs(120),
+            },
+        ));
+        assert!(
+            block_on(opentok.create_session_for_room("mancha", SessionOptions::default())).is_err()
+        );
+    }
+
+    /// `HttpClient` creating a new session for every request, slowly enough
+    /// for concurrent calls to overlap.
+    #[derive(Default)]
+    struct SlowClient {
+        created: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl HttpClient for Arc<SlowClient> {
+        async fn send(&self, _: HttpRequest) -> Result<HttpResponse, OpenTokError> {
+            let created = self.created.fetch_add(1, Ordering::SeqCst);
+            FuturesTimer.sleep(Duration::from_millis(20)).await;
+            Ok(HttpResponse {
+                status: 200,
+                headers: vec![],
+                body: format!(r#"[{{"session_id":"1_MX{}"}}]"#, created).into_bytes(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_session_lock() {
+        let client = Arc::new(SlowClient::default());
+        let opentok = OpenTok::builder("sancho".into(), "quijote".into())
+            .with_http_client(client.clone())
+            .with_session_fallback(
+                Arc::new(InMemorySessionStore::new()),
+                Duration::from_secs(60),
+            )
+            .with_session_lock(Arc::new(InMemorySessionLock::new()))
+            .build();
+        let (first, second, other) = block_on(async {
+            join!(
+                opentok.create_session_for_room("mancha", SessionOptions::default()),
+                opentok.create_session_for_room("mancha", SessionOptions::default()),
+                opentok.create_session_for_room("toboso", SessionOptions::default()),
+            )
+        });
+        let (first, second, other) = (first.unwrap(), second.unwrap(), other.unwrap());
+        assert_eq!(first.session_id, second.session_id);
+        assert!(!first.degraded);
+        assert_ne!(first.session_id, other.session_id);
+        assert_eq!(client.created.load(Ordering::SeqCst), 2);
+
+        let later =
+            block_on(opentok.create_session_for_room("mancha", SessionOptions::default())).unwrap();
+        assert_ne!(later.session_id, first.session_id);
+        assert_eq!(client.created.load(Ordering::SeqCst), 3);
+
+        // Sessions created concurrently are told apart by their ID, not by
+        // when they were created.
+        let client = Arc::new(SlowClient::default());
+        let opentok = OpenTok::builder("sancho".into(), "quijote".into())
+            .with_http_client(client.clone())
+            .with_session_fallback(
+                Arc::new(InMemorySessionStore::new()),
+                Duration::from_secs(60),
+            )
+            .with_session_lock(Arc::new(InMemorySessionLock::new()))
+            .with_time_source(FixedTime(UNIX_EPOCH))
+            .build();
+        let (first, second) = block_on(async {
+            join!(
+                opentok.create_session_for_room("mancha", SessionOptions::default()),
+                opentok.create_session_for_room("mancha", SessionOptions::default()),
+            )
+        });
+        assert_eq!(first.unwrap().session_id, second.unwrap().session_id);
+        assert_eq!(client.created.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_reused_session() {
+        let store = Arc::new(InMemorySessionStore::new());
+        let opentok = OpenTok::builder("sancho".into(), "quijote".into())
+            .with_http_client(Arc::new(SlowClient::default()))
+            .with_session_fallback(store.clone(), Duration::from_secs(60))
+            .with_session_lock(Arc::new(InMemorySessionLock::new()))
+            .build();
+        let (first, second, third) = block_on(async {
+            join!(
+                opentok.create_session_for_room("mancha", SessionOptions::default()),
+                opentok.create_session_for_room("mancha", SessionOptions::default()),
+                opentok.create_session_for_room("mancha", SessionOptions::default()),
+            )
+        });
+        let sessions = [first.unwrap(), second.unwrap(), third.unwrap()];
+        let created: Vec<_> = sessions.iter().filter(|session| !session.reused).collect();
+        assert_eq!(created.len(), 1);
+        assert!(sessions
+            .iter()
+            .all(|session| session.session_id == created[0].session_id && !session.degraded));
+
+        let opentok = OpenTok::builder("sancho".into(), "quijote".into())
+            .with_http_client(UnreachableClient)
+            .with_session_fallback(store, Duration::from_secs(60))
+            .build();
+        let degraded =
+            block_on(opentok.create_session_for_room("mancha", SessionOptions::default())).unwrap();
+        assert!(degraded.reused && degraded.degraded);
+    }
+
+    #[test]
+    fn test_session_time_source() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let store = Arc::new(InMemorySessionStore::new());
+        let opentok = OpenTok::builder("sancho".into(), "quijote".into())
+            .with_http_client(Arc::new(SlowClient::default()))
+            .with_session_fallback(store.clone(), Duration::from_secs(60))
+            .with_time_source(FixedTime(time))
+            .build();
+        let session =
+            block_on(opentok.create_session_for_room("mancha", SessionOptions::default())).unwrap();
+        assert_eq!(session.created_at, time);
+        assert_eq!(
+            session.age(time + Duration::from_secs(30)),
+            Duration::from_secs(30)
+        );
+        assert_eq!(block_on(store.get("mancha")).unwrap().created_at, time);
+
+        let opentok = OpenTok::builder("sancho".into(), "quijote".into())
+            .with_http_client(UnreachableClient)
+            .with_session_fallback(store, Duration::from_secs(60))
+            .with_time_source(FixedTime(time + Duration::from_secs(61)))
+            .build();
+        assert!(
+            block_on(opentok.create_session_for_room("mancha", SessionOptions::default())).is_err()
+        );
+    }
+}
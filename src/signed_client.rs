@@ -0,0 +1,189 @@
+//! Extension point for wrapping OpenTok API endpoints that this crate does
+//! not cover yet, such as beta ones, from other crates.
+
+use crate::endpoint::Endpoint;
+use crate::http_client::{body_string, check_status};
+use crate::time::SystemTime;
+use crate::{HttpMethod, HttpResponse, OpenTok, OpenTokError};
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Client sending requests to the OpenTok API signed with the credentials
+/// of a project, as implemented by `OpenTok`. Requests go through the
+/// same authentication, retries, timeouts, rate limiting and middlewares
+/// as the operations of `OpenTok` itself.
+///
+/// Endpoints are built from `SignedClient::project_endpoint`, or
+/// `Endpoint::root` for those not scoped to a project, with
+/// `Endpoint::segment` and `Endpoint::query`, so that IDs received from
+/// clients cannot alter the path of the request. The typed helpers encode
+/// request bodies and decode response bodies as JSON, and fail with the
+/// same errors as the rest of the crate for non 2xx responses.
+#[async_trait]
+pub trait SignedClient: Send + Sync {
+    /// Root of the endpoints scoped to the project of the credentials.
+    fn project_endpoint(&self) -> Endpoint;
+
+    /// Sends a signed request to `endpoint` with `body`, already JSON
+    /// encoded, if any, and hands back the response whatever its status
+    /// code is.
+    async fn send_signed(
+        &self,
+        method: HttpMethod,
+        endpoint: &Endpoint,
+        body: Option<Vec<u8>>,
+    ) -> Result<HttpResponse, OpenTokError>;
+
+    /// Sends a GET request and decodes the response body.
+    async fn get<T: DeserializeOwned>(&self, endpoint: &Endpoint) -> Result<T, OpenTokError> {
+        decode_json(self.send_signed(HttpMethod::Get, endpoint, None).await?)
+    }
+
+    /// Sends a POST request with `body` and decodes the response body.
+    async fn post<B: Serialize + Sync, T: DeserializeOwned>(
+        &self,
+        endpoint: &Endpoint,
+        body: &B,
+    ) -> Result<T, OpenTokError> {
+        let body = encode(body)?;
+        decode_json(self.send_signed(HttpMethod::Post, endpoint, body).await?)
+    }
+
+    /// Sends a PUT request with `body` and decodes the response body.
+    async fn put<B: Serialize + Sync, T: DeserializeOwned>(
+        &self,
+        endpoint: &Endpoint,
+        body: &B,
+    ) -> Result<T, OpenTokError> {
+        let body = encode(body)?;
+        decode_json(self.send_signed(HttpMethod::Put, endpoint, body).await?)
+    }
+
+    /// Sends a PATCH request with `body` and decodes the response body.
+    async fn patch<B: Serialize + Sync, T: DeserializeOwned>(
+        &self,
+        endpoint: &Endpoint,
+        body: &B,
+    ) -> Result<T, OpenTokError> {
+        let body = encode(body)?;
+        decode_json(self.send_signed(HttpMethod::Patch, endpoint, body).await?)
+    }
+
+    /// Sends a DELETE request, ignoring the response body.
+    async fn delete(&self, endpoint: &Endpoint) -> Result<(), OpenTokError> {
+        let response = self.send_signed(HttpMethod::Delete, endpoint, None).await?;
+        check_status(response, SystemTime::now()).map(|_| ())
+    }
+}
+
+fn encode(body: &impl Serialize) -> Result<Option<Vec<u8>>, OpenTokError> {
+    serde_json::to_vec(body)
+        .map(Some)
+        .map_err(|_| OpenTokError::EncodingError)
+}
+
+/// Decodes the JSON body of a 2xx `response`, failing as the operations of
+/// `OpenTok` do otherwise. An empty body decodes as `null`, so that
+/// responses without content can be decoded as `()`. The delay of rate
+/// limited responses is measured from the system clock when given as a date.
+pub fn decode_json<T: DeserializeOwned>(response: HttpResponse) -> Result<T, OpenTokError> {
+    let body = body_string(check_status(response, SystemTime::now())?)?;
+    let json = if body.is_empty() { "null" } else { &body };
+    serde_json::from_str(json).map_err(|_| OpenTokError::UnexpectedResponse(body.clone()))
+}
+
+#[async_trait]
+impl SignedClient for OpenTok {
+    fn project_endpoint(&self) -> Endpoint {
+        Endpoint::project(&self.credentials.credentials().api_key)
+    }
+
+    async fn send_signed(
+        &self,
+        method: HttpMethod,
+        endpoint: &Endpoint,
+        body: Option<Vec<u8>>,
+    ) -> Result<HttpResponse, OpenTokError> {
+        self.bounded(
+            self.client
+                .send_json(method, endpoint, self.credentials.as_ref(), body),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::CannedClient;
+
+    use futures::executor::block_on;
+    use serde::Deserialize;
+    use std::sync::Arc;
+
+    fn beta(status: u16, body: &'static str) -> (OpenTok, Arc<CannedClient>) {
+        let endpoint = CannedClient::new(status, body);
+        let opentok = OpenTok::builder("1234".into(), "quijote".into())
+            .with_http_client(endpoint.clone())
+            .build();
+        (opentok, endpoint)
+    }
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Caption {
+        id: String,
+    }
+
+    #[test]
+    fn test_signed_client() {
+        let (opentok, endpoint) = beta(200, r#"{"id":"c1"}"#);
+        let captions = opentok.project_endpoint().segment("captions");
+        assert_eq!(
+            block_on(opentok.post::<_, Caption>(&captions, &Caption { id: "c0".into() })),
+            Ok(Caption { id: "c1".into() })
+        );
+        assert_eq!(
+            block_on(opentok.get::<Caption>(&captions.clone().segment("c1"))),
+            Ok(Caption { id: "c1".into() })
+        );
+        let requests = endpoint.requests();
+        assert_eq!(requests[0].method, HttpMethod::Post);
+        assert_eq!(
+            requests[0].url,
+            "https://api.opentok.com/v2/project/1234/captions"
+        );
+        assert_eq!(requests[0].body, Some(br#"{"id":"c0"}"#.to_vec()));
+        assert!(requests[0]
+            .headers
+            .contains(&("Content-Type".into(), "application/json".into())));
+        assert_eq!(
+            requests[1].url,
+            "https://api.opentok.com/v2/project/1234/captions/c1"
+        );
+        assert_eq!(requests[1].body, None);
+    }
+
+    #[test]
+    fn test_signed_client_errors() {
+        let (opentok, _) = beta(204, "");
+        let caption = opentok.project_endpoint().segment("captions").segment("c1");
+        assert_eq!(block_on(opentok.delete(&caption)), Ok(()));
+        assert_eq!(block_on(opentok.put::<_, ()>(&caption, &())), Ok(()));
+        assert!(matches!(
+            block_on(opentok.get::<Caption>(&caption)),
+            Err(OpenTokError::UnexpectedResponse(_))
+        ));
+
+        let (opentok, _) = beta(404, r#"{"code":404,"message":"Not found"}"#);
+        assert!(matches!(
+            block_on(opentok.get::<Caption>(&caption)),
+            Err(OpenTokError::NotFound(_))
+        ));
+        assert!(matches!(
+            block_on(opentok.get::<Caption>(&opentok.project_endpoint().segment(".."))),
+            Err(OpenTokError::BadRequest(_))
+        ));
+    }
+}
@@ -0,0 +1,45 @@
+//! Sources of the timestamps and nonces of the tokens and JWTs signed by
+//! `OpenTok`, replaceable to make them deterministic in tests.
+
+use crate::time::{SystemTime, UNIX_EPOCH};
+
+use rand::Rng;
+
+/// Source of the current time, set with `OpenTokBuilder::with_time_source`.
+pub trait TimeSource: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// `TimeSource` reading the system clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemTimeSource;
+
+impl TimeSource for SystemTimeSource {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Source of the nonces of tokens and of the IDs of JWTs, set with
+/// `OpenTokBuilder::with_nonce_source`. Nonces must be unpredictable
+/// outside of tests.
+pub trait NonceSource: Send + Sync {
+    fn nonce(&self) -> u64;
+}
+
+/// `NonceSource` drawing nonces from the thread-local random generator.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RandomNonceSource;
+
+impl NonceSource for RandomNonceSource {
+    fn nonce(&self) -> u64 {
+        rand::thread_rng().gen()
+    }
+}
+
+/// Seconds elapsed since the Unix epoch at `time`, or 0 for earlier times.
+pub(crate) fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,51 @@
+//! Stubs shared by the tests of several modules.
+
+use crate::time::SystemTime;
+use crate::{HttpClient, HttpRequest, HttpResponse, OpenTokError, TimeSource};
+
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+
+/// `TimeSource` always returning the same time.
+pub struct FixedTime(pub SystemTime);
+
+impl TimeSource for FixedTime {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
+
+/// `HttpClient` answering every request with `status` and `body`, and
+/// keeping the requests it was sent.
+pub struct CannedClient {
+    status: u16,
+    body: &'static str,
+    requests: Mutex<Vec<HttpRequest>>,
+}
+
+impl CannedClient {
+    pub fn new(status: u16, body: &'static str) -> Arc<Self> {
+        Arc::new(Self {
+            status,
+            body,
+            requests: Mutex::new(vec![]),
+        })
+    }
+
+    /// The requests sent so far, oldest first.
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl HttpClient for Arc<CannedClient> {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, OpenTokError> {
+        self.requests.lock().unwrap().push(request);
+        Ok(HttpResponse {
+            status: self.status,
+            headers: vec![],
+            body: self.body.as_bytes().to_vec(),
+        })
+    }
+}
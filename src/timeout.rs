@@ -0,0 +1,65 @@
+use crate::{OpenTokError, Timer};
+
+use futures::future::{self, Either};
+use futures::Future;
+use std::time::Duration;
+
+/// Bounds on how long requests to the OpenTok API can take.
+/// Requests exceeding any of them fail with `OpenTokError::Timeout`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Timeouts {
+    /// Maximum time to establish a connection to the OpenTok servers.
+    pub connect: Option<Duration>,
+    /// Maximum time for a whole API call, including retries and reading
+    /// the response body.
+    pub total: Option<Duration>,
+}
+
+/// Resolves to `future`'s output, or to `OpenTokError::Timeout` if it takes
+/// longer than `timeout`, as measured by `timer`.
+pub(crate) async fn timeout<T>(
+    timer: &dyn Timer,
+    timeout: Option<Duration>,
+    future: impl Future<Output = Result<T, OpenTokError>>,
+) -> Result<T, OpenTokError> {
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return future.await,
+    };
+    futures::pin_mut!(future);
+    match future::select(future, timer.sleep(timeout)).await {
+        Either::Left((output, _)) => output,
+        Either::Right(_) => Err(OpenTokError::Timeout),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::FuturesTimer;
+
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_timeout() {
+        let never = future::pending::<Result<(), OpenTokError>>();
+        assert_eq!(
+            block_on(timeout(
+                &FuturesTimer,
+                Some(Duration::from_millis(10)),
+                never
+            )),
+            Err(OpenTokError::Timeout)
+        );
+        let ready = future::ready(Ok(42));
+        assert_eq!(
+            block_on(timeout(&FuturesTimer, Some(Duration::from_secs(10)), ready)),
+            Ok(42)
+        );
+        assert_eq!(
+            block_on(timeout(&FuturesTimer, None, future::ready(Ok(42)))),
+            Ok(42)
+        );
+    }
+}
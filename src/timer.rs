@@ -0,0 +1,97 @@
+use futures::future::{BoxFuture, FutureExt};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Source of the sleeps used for retry backoffs, timeouts and rate
+/// limiting, so that they run on the consumer's async runtime.
+/// Set with `OpenTokBuilder::with_timer`. By default, the timer of the
+/// runtime selected with the `runtime-*` features is used, and
+/// `FuturesTimer` otherwise.
+pub trait Timer: Send + Sync {
+    /// Future completing after `duration`.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// `Timer` backed by futures-timer, which runs its own timer thread and so
+/// works with any executor.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FuturesTimer;
+
+impl Timer for FuturesTimer {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        futures_timer::Delay::new(duration).boxed()
+    }
+}
+
+/// `Timer` backed by tokio. Sleeps must be awaited from within a tokio
+/// runtime with the time driver enabled.
+#[cfg(feature = "runtime-tokio")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokioTimer;
+
+#[cfg(feature = "runtime-tokio")]
+impl Timer for TokioTimer {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        tokio::time::sleep(duration).boxed()
+    }
+}
+
+/// `Timer` backed by async-std.
+#[cfg(feature = "runtime-async-std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AsyncStdTimer;
+
+#[cfg(feature = "runtime-async-std")]
+impl Timer for AsyncStdTimer {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        async_std::task::sleep(duration).boxed()
+    }
+}
+
+/// `Timer` backed by async-io, the reactor of smol.
+#[cfg(feature = "runtime-smol")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SmolTimer;
+
+#[cfg(feature = "runtime-smol")]
+impl Timer for SmolTimer {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        async_io::Timer::after(duration).map(|_| ()).boxed()
+    }
+}
+
+/// The timer used when none is provided. When several runtime features are
+/// enabled, tokio takes precedence over async-std, and async-std over smol.
+pub(crate) fn default_timer() -> Arc<dyn Timer> {
+    #[cfg(feature = "runtime-tokio")]
+    let timer = TokioTimer;
+    #[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+    let timer = AsyncStdTimer;
+    #[cfg(all(
+        feature = "runtime-smol",
+        not(any(feature = "runtime-tokio", feature = "runtime-async-std"))
+    ))]
+    let timer = SmolTimer;
+    #[cfg(not(any(
+        feature = "runtime-tokio",
+        feature = "runtime-async-std",
+        feature = "runtime-smol"
+    )))]
+    let timer = FuturesTimer;
+    Arc::new(timer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::executor::block_on;
+    use std::time::Instant;
+
+    #[test]
+    fn test_futures_timer() {
+        let start = Instant::now();
+        block_on(FuturesTimer.sleep(Duration::from_millis(10)));
+        assert!(start.elapsed() >= Duration::from_millis(10));
+    }
+}
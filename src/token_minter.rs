@@ -0,0 +1,213 @@
+use crate::http_client::{self, body_string, check_status};
+use crate::sources::unix_secs;
+use crate::{
+    Credentials, CredentialsProvider, HttpClient, HttpMethod, HttpRequest, NonceSource,
+    OpenTokError, PoolConfig, RandomNonceSource, SessionId, SystemTimeSource, TimeSource, Timeouts,
+    TokenRole,
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Generator of the tokens clients connect to sessions with, set with
+/// `OpenTokBuilder::with_token_minter` and used by `OpenTok::mint_token`.
+/// Lets organizations keep the API secret in a single hardened signing
+/// service, reached with `RemoteTokenMinter`, while using this crate
+/// everywhere else.
+#[async_trait]
+pub trait TokenMinter: Send + Sync {
+    async fn mint(&self, session_id: &SessionId, role: TokenRole) -> Result<String, OpenTokError>;
+}
+
+/// `TokenMinter` signing tokens locally with the API secret. `OpenTok`
+/// signs the tokens of `OpenTok::generate_token`, and those of
+/// `OpenTok::mint_token` when no other minter is set, with one sharing its
+/// credentials, time and nonce sources and token clock skew.
+#[derive(Clone)]
+pub struct LocalTokenMinter {
+    credentials: Arc<dyn CredentialsProvider>,
+    time_source: Arc<dyn TimeSource>,
+    nonce_source: Arc<dyn NonceSource>,
+    clock_skew: Duration,
+}
+
+impl LocalTokenMinter {
+    pub fn new(api_key: String, api_secret: String) -> Self {
+        Self::with_sources(
+            Arc::new(Credentials::new(api_key, api_secret)),
+            Arc::new(SystemTimeSource),
+            Arc::new(RandomNonceSource),
+            Duration::from_secs(0),
+        )
+    }
+
+    pub(crate) fn with_sources(
+        credentials: Arc<dyn CredentialsProvider>,
+        time_source: Arc<dyn TimeSource>,
+        nonce_source: Arc<dyn NonceSource>,
+        clock_skew: Duration,
+    ) -> Self {
+        Self {
+            credentials,
+            time_source,
+            nonce_source,
+            clock_skew,
+        }
+    }
+
+    /// See `OpenTokBuilder::with_time_source`.
+    pub fn with_time_source(mut self, source: impl TimeSource + 'static) -> Self {
+        self.time_source = Arc::new(source);
+        self
+    }
+
+    /// See `OpenTokBuilder::with_nonce_source`.
+    pub fn with_nonce_source(mut self, source: impl NonceSource + 'static) -> Self {
+        self.nonce_source = Arc::new(source);
+        self
+    }
+
+    /// See `OpenTokBuilder::with_token_clock_skew`.
+    pub fn with_clock_skew(mut self, skew: Duration) -> Self {
+        self.clock_skew = skew;
+        self
+    }
+
+    pub(crate) fn sign(&self, session_id: &SessionId, role: TokenRole) -> String {
+        let credentials = self.credentials.credentials();
+        crate::sign_token(
+            &credentials.api_key,
+            &credentials.api_secret,
+            session_id,
+            role,
+            unix_secs(self.time_source.now()),
+            self.clock_skew.as_secs(),
+            self.nonce_source.nonce(),
+        )
+    }
+}
+
+#[async_trait]
+impl TokenMinter for LocalTokenMinter {
+    async fn mint(&self, session_id: &SessionId, role: TokenRole) -> Result<String, OpenTokError> {
+        Ok(self.sign(session_id, role))
+    }
+}
+
+#[derive(Serialize)]
+struct MintRequest<'a> {
+    session_id: &'a SessionId,
+    role: String,
+}
+
+#[derive(Deserialize)]
+struct MintResponse {
+    token: String,
+}
+
+/// `TokenMinter` asking a signing service for tokens.
+/// Every token is requested with a POST of the JSON object
+/// `{"session_id": ..., "role": ...}`, the role being `publisher`,
+/// `subscriber` or `moderator`, to which the service must answer with a
+/// 2xx status and the JSON object `{"token": ...}`.
+pub struct RemoteTokenMinter {
+    url: String,
+    headers: Vec<(String, String)>,
+    http: Arc<dyn HttpClient>,
+}
+
+impl RemoteTokenMinter {
+    /// Requests tokens from the signing service at `url`, through the
+    /// default HTTP backend.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.into(),
+            headers: vec![],
+            http: http_client::default_http_client(
+                &Timeouts::default(),
+                None,
+                &PoolConfig::default(),
+            ),
+        }
+    }
+
+    /// Sends `name: value` with every request, for instance to
+    /// authenticate to the signing service.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sends requests through `client` instead of the default HTTP backend.
+    pub fn with_http_client(mut self, client: impl HttpClient + 'static) -> Self {
+        self.http = Arc::new(client);
+        self
+    }
+}
+
+#[async_trait]
+impl TokenMinter for RemoteTokenMinter {
+    async fn mint(&self, session_id: &SessionId, role: TokenRole) -> Result<String, OpenTokError> {
+        let body = serde_json::to_vec(&MintRequest {
+            session_id,
+            role: role.to_string(),
+        })
+        .map_err(|_| OpenTokError::EncodingError)?;
+        let mut headers = vec![
+            ("Accept".to_string(), "application/json".to_string()),
+            ("Content-Type".to_string(), "application/json".to_string()),
+        ];
+        headers.extend(self.headers.iter().cloned());
+        let response = self
+            .http
+            .send(HttpRequest {
+                method: HttpMethod::Post,
+                url: self.url.clone(),
+                headers,
+                body: Some(body),
+            })
+            .await?;
+        let body = body_string(check_status(response, SystemTimeSource.now())?)?;
+        serde_json::from_str::<MintResponse>(&body)
+            .map(|response| response.token)
+            .map_err(|_| OpenTokError::UnexpectedResponse(body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::CannedClient;
+
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_local_token_minter() {
+        let minter = LocalTokenMinter::new("sancho".into(), "quijote".into());
+        let token = block_on(minter.mint(&"1_MX4".into(), TokenRole::Publisher)).unwrap();
+        assert!(token.starts_with("T1=="));
+    }
+
+    #[test]
+    fn test_remote_token_minter() {
+        let service = CannedClient::new(200, r#"{"token":"T1==signed"}"#);
+        let minter = RemoteTokenMinter::new("https://signer.example.com/tokens")
+            .with_header("Authorization", "Bearer dulcinea")
+            .with_http_client(service.clone());
+        assert_eq!(
+            block_on(minter.mint(&"1_MX4".into(), TokenRole::Moderator)),
+            Ok("T1==signed".into())
+        );
+        let requests = service.requests();
+        assert_eq!(requests[0].url, "https://signer.example.com/tokens");
+        assert_eq!(
+            requests[0].body,
+            Some(br#"{"session_id":"1_MX4","role":"moderator"}"#.to_vec())
+        );
+        assert!(requests[0]
+            .headers
+            .contains(&("Authorization".into(), "Bearer dulcinea".into())));
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+
+/// Billable operation performed on behalf of an API key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UsageEvent {
+    /// A session was created with `OpenTok::create_session`.
+    SessionCreated,
+    /// A token was generated with `OpenTok::generate_token`.
+    TokenGenerated,
+}
+
+/// Receiver of usage events, for multi-tenant platforms doing internal
+/// chargeback per API key. Set it with `OpenTokBuilder::with_usage_sink`.
+///
+/// `record` is called synchronously right after the operation succeeded,
+/// so implementations should not block.
+pub trait UsageSink: Send + Sync {
+    fn record(&self, api_key: &str, event: UsageEvent);
+}
+
+/// `UsageSink` keeping per API key counters in memory.
+#[derive(Debug, Default)]
+pub struct UsageCounters {
+    counters: Mutex<HashMap<(String, UsageEvent), u64>>,
+}
+
+impl UsageCounters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of times `event` was recorded for `api_key`.
+    pub fn count(&self, api_key: &str, event: UsageEvent) -> u64 {
+        self.counters
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&(api_key.to_owned(), event))
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+impl UsageSink for UsageCounters {
+    fn record(&self, api_key: &str, event: UsageEvent) {
+        *self
+            .counters
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry((api_key.to_owned(), event))
+            .or_insert(0) += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OpenTok, TokenRole};
+
+    use std::sync::Arc;
+
+    #[test]
+    fn test_usage_counters() {
+        let counters = Arc::new(UsageCounters::new());
+        let opentok = OpenTok::builder("sancho".into(), "quijote".into())
+            .with_usage_sink(counters.clone())
+            .build();
+        opentok.generate_token(&"session".into(), TokenRole::Publisher);
+        opentok.generate_token(&"session".into(), TokenRole::Subscriber);
+        assert_eq!(counters.count("sancho", UsageEvent::TokenGenerated), 2);
+        assert_eq!(counters.count("sancho", UsageEvent::SessionCreated), 0);
+        assert_eq!(counters.count("rocinante", UsageEvent::TokenGenerated), 0);
+    }
+}
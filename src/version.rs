@@ -0,0 +1,13 @@
+/// Version of the OpenTok REST API the requests of this crate are written
+/// against.
+pub const API_VERSION: &str = "v2";
+
+/// Version of this crate.
+pub const SDK_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Version of the OpenTok REST API the requests of this crate are written
+/// against, so that services can check at startup that it matches the API
+/// features they rely on.
+pub fn api_version() -> &'static str {
+    API_VERSION
+}